@@ -0,0 +1,75 @@
+use std::{
+    env,
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use unicode_id_trie_rle_derived_core_properties::{
+    IdentifierProperties, to_identifier_bits,
+};
+
+/// The last codepoint in the dense table. Everything above it is looked up in
+/// the trie.
+const MAX_DENSE_CODEPOINT: u32 = 0xffff;
+const ELEMENTS_PER_LINE: usize = 3;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?);
+    let derived = manifest_dir.join("../DerivedCoreProperties.txt");
+    println!("cargo:rerun-if-changed={}", derived.display());
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let file = File::open(&derived)?;
+    let parsed = unicode_id_trie_rle_derived_core_properties::parse(file)?;
+
+    let table = to_identifier_bits(&parsed, IdentifierProperties::Xid);
+
+    // The same layout as `baseline`: 32 codepoints of 2 bits each per u64.
+    let buffer = table[..=MAX_DENSE_CODEPOINT as usize]
+        .chunks(32)
+        .map(|chunk| {
+            chunk.iter().enumerate().fold(0u64, |value, (j, &bits)| {
+                value | (bits as u64) << (j * 2)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    let out_path = out_dir.join("table.rs");
+    let out_file = File::create(&out_path)?;
+    let mut writer = BufWriter::new(out_file);
+
+    writeln!(writer, "// Code generated by build.rs; DO NOT EDIT.")?;
+    writeln!(
+        writer,
+        "pub(crate) const MAX_DENSE_CODEPOINT: u32 = {MAX_DENSE_CODEPOINT:#x};"
+    )?;
+    writeln!(
+        writer,
+        "pub(crate) static BMP_TABLE: [u64; {}] = [",
+        buffer.len()
+    )?;
+
+    for (idx, value) in buffer.iter().enumerate() {
+        if idx % ELEMENTS_PER_LINE == 0 {
+            write!(writer, "\t")?;
+        }
+
+        write!(writer, "0x{value:016x},")?;
+
+        if idx % ELEMENTS_PER_LINE == ELEMENTS_PER_LINE - 1
+            || idx + 1 == buffer.len()
+        {
+            writeln!(writer)?;
+        } else {
+            write!(writer, " ")?;
+        }
+    }
+
+    writeln!(writer, "];")?;
+    writer.flush()?;
+
+    Ok(())
+}
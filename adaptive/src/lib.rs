@@ -0,0 +1,153 @@
+//! Classifies Basic Multilingual Plane codepoints with a dense table like
+//! `baseline`'s and everything above with the `unicode-id-trie-rle` trie.
+
+pub use unicode_id_trie_rle::UnicodeIdentifierClass;
+
+const IDENTIFIER_START: u64 = 1;
+const IDENTIFIER_CONTINUE: u64 = 2;
+
+include!(concat!(env!("OUT_DIR"), "/table.rs"));
+
+/// The number of bytes of static storage used by the lookup tables, which is
+/// the dense BMP table plus all of `unicode-id-trie-rle`'s tables.
+pub const TABLE_BYTES: usize =
+    size_of_val(&BMP_TABLE) + unicode_id_trie_rle::TABLE_BYTES;
+
+/// Returns whether the codepoint specified has the properties `XID_Start` or
+/// `XID_Continue`.
+#[inline]
+pub fn unicode_identifier_class(cp: char) -> UnicodeIdentifierClass {
+    let cp32 = cp as u32;
+    if cp32 > MAX_DENSE_CODEPOINT {
+        return unicode_id_trie_rle::unicode_identifier_class(cp);
+    }
+
+    let bits = BMP_TABLE[(cp32 >> 5) as usize] >> ((cp32 & 31) * 2);
+    UnicodeIdentifierClass::new(
+        bits & IDENTIFIER_START != 0,
+        bits & IDENTIFIER_CONTINUE != 0,
+    )
+}
+
+/// Checks if a codepoint is a unicode identifier, defined by
+/// Unicode Standard Annex #31.
+#[inline]
+pub fn is_identifier(cp: &[char]) -> bool {
+    if cp.is_empty() {
+        return false;
+    }
+
+    if !unicode_identifier_class(cp[0]).is_start() {
+        return false;
+    }
+
+    for (i, c) in cp.iter().enumerate() {
+        if !unicode_identifier_class(*c).is_continue() {
+            // the two special characters are only allowed in the
+            // middle, not the end.
+            if (*c != '\u{200c}' && *c != '\u{200d}') || i + 1 == cp.len() {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+/// Returns the [UnicodeIdentifierClass] of the first `char` in `s`, or `None`
+/// if `s` is empty.
+#[inline]
+pub fn first_char_class(s: &str) -> Option<UnicodeIdentifierClass> {
+    s.chars().next().map(unicode_identifier_class)
+}
+
+/// Checks if a given string is a unicode identifier, defined by Unicode
+/// Standard Annex #31.
+#[inline]
+pub fn str_is_identifier(s: &str) -> bool {
+    let mut iter = s.chars().peekable();
+    let Some(first) = iter.next() else {
+        return false;
+    };
+
+    if !unicode_identifier_class(first).is_start() {
+        return false;
+    }
+
+    while let Some(c) = iter.next() {
+        if !unicode_identifier_class(c).is_continue() {
+            // the two special characters are only allowed in the
+            // middle, not the end.
+            if (c != '\u{200c}' && c != '\u{200d}') || iter.peek().is_none() {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::sync::OnceLock;
+    use unicode_id_trie_rle_derived_core_properties::{
+        IdentifierProperties, parse_default, to_identifier_bits,
+    };
+
+    fn derived_identifier_table() -> &'static [u8] {
+        static TABLE: OnceLock<Box<[u8]>> = OnceLock::new();
+        TABLE.get_or_init(|| {
+            to_identifier_bits(parse_default(), IdentifierProperties::Xid)
+        })
+    }
+
+    #[test]
+    fn unicode_identifier_class_matches_derived_core_properties() {
+        let table = derived_identifier_table();
+        for cp in 0..=0x10ffff {
+            let Some(ch) = char::from_u32(cp) else {
+                continue;
+            };
+            let expected = table[ch as usize];
+            let class = unicode_identifier_class(ch);
+            assert_eq!(
+                class.is_start(),
+                expected & IDENTIFIER_START as u8 != 0,
+                "XID_Start mismatch at U+{cp:04X}"
+            );
+            assert_eq!(
+                class.is_continue(),
+                expected & IDENTIFIER_CONTINUE as u8 != 0,
+                "XID_Continue mismatch at U+{cp:04X}"
+            );
+        }
+    }
+
+    #[test]
+    fn table_bytes_counts_both_tables() {
+        assert_eq!(TABLE_BYTES, 16 * 1024 + unicode_id_trie_rle::TABLE_BYTES);
+    }
+
+    proptest! {
+        #[test]
+        fn matches_unicode_id_trie_rle(cp in any::<char>()) {
+            let ours = unicode_identifier_class(cp);
+            let trie = unicode_id_trie_rle::unicode_identifier_class(cp);
+            prop_assert_eq!(ours.is_start(), trie.is_start());
+            prop_assert_eq!(ours.is_continue(), trie.is_continue());
+        }
+
+        #[test]
+        fn str_and_slice_identifier_agree(chars in prop::collection::vec(any::<char>(), 0..16)) {
+            let string: String = chars.iter().copied().collect();
+            prop_assert_eq!(
+                str_is_identifier(&string),
+                is_identifier(&chars),
+                "str/is_identifier disagreement on {:?}",
+                string
+            );
+        }
+    }
+}
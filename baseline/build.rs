@@ -1,5 +1,4 @@
 use std::{
-    collections::BTreeMap,
     env,
     error::Error,
     fs::File,
@@ -7,6 +6,10 @@ use std::{
     path::PathBuf,
 };
 
+use unicode_id_trie_rle_derived_core_properties::{
+    IdentifierProperties, to_identifier_bits,
+};
+
 const MAX_CODEPOINT: u32 = 0x10ffff;
 const ELEMENTS_PER_LINE: usize = 3;
 
@@ -19,27 +22,10 @@ fn main() -> Result<(), Box<dyn Error>> {
     let file = File::open(&derived)?;
     let parsed = unicode_id_trie_rle_derived_core_properties::parse(file)?;
 
-    // Convert the parsed file into a map from codepoint => 2 bit value
-    let parsed = parsed
-        .iter()
-        .filter(|(_, v)| v.contains("XID_Start") || v.contains("XID_Continue"))
-        .map(|(k, v)| {
-            (
-                *k,
-                v.iter()
-                    .map(|x| {
-                        if x.contains("XID_Start") {
-                            1 << 0
-                        } else if x.contains("XID_Continue") {
-                            1 << 1
-                        } else {
-                            0
-                        }
-                    })
-                    .fold(0, |acc, x| acc | x),
-            )
-        })
-        .collect::<BTreeMap<u32, u64>>();
+    let table = to_identifier_bits(&parsed, IdentifierProperties::Xid);
+    // the words are stored so their bytes are in little endian order on
+    // every target, which `dense_table` relies on.
+    let big_endian = env::var("CARGO_CFG_TARGET_ENDIAN")? == "big";
 
     let mut buffer = Vec::new();
     let mut i = 0;
@@ -51,7 +37,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
         for j in 0..=limit {
             let c = i + j;
-            value |= parsed.get(&c).unwrap_or(&0) << j * 2;
+            value |= (table[c as usize] as u64) << (j * 2);
+        }
+        if big_endian {
+            value = value.swap_bytes();
         }
         buffer.push(value);
 
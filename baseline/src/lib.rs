@@ -4,6 +4,9 @@ const IDENTIFIER_CONTINUE: u8 = 2;
 
 include!(concat!(env!("OUT_DIR"), "/table.rs"));
 
+/// The number of bytes of static storage used by the lookup table.
+pub const TABLE_BYTES: usize = size_of_val(&IDENTIFIER_TABLE);
+
 pub struct UnicodeIdentifierClass(u8);
 
 impl UnicodeIdentifierClass {
@@ -27,16 +30,37 @@ pub fn unicode_identifier_class(cp: char) -> UnicodeIdentifierClass {
     }
 
     let shift = (cp as u32 & 31) * 2;
-    return UnicodeIdentifierClass(
-        ((IDENTIFIER_TABLE[(cp as u32 >> 5) as usize] >> shift) & 3) as u8,
-    );
+    let word = u64::from_le(IDENTIFIER_TABLE[(cp as u32 >> 5) as usize]);
+    UnicodeIdentifierClass(((word >> shift) & 3) as u8)
+}
+
+/// Returns the packed table [unicode_identifier_class] looks codepoints up in,
+/// for callers which want to index it directly.
+///
+/// Every codepoint from `U+0000` to `U+10FFFF` has 2 bits, packed little
+/// endian four to a byte, so the bits for codepoint `cp` are
+/// `(table[cp >> 2] >> ((cp & 3) * 2)) & 3`. The low bit means `XID_Start`
+/// and the high bit means `XID_Continue`. Surrogates have no bits set.
+///
+/// This is the same memory the lookups read as `u64` words, which `build.rs`
+/// stores little endian so the byte layout doesn't depend on the target.
+#[inline]
+pub fn dense_table() -> &'static [u8] {
+    // SAFETY: `u8` has no alignment or validity requirements, and the slice
+    // covers exactly the bytes of `IDENTIFIER_TABLE`, which is never mutated.
+    unsafe {
+        core::slice::from_raw_parts(
+            IDENTIFIER_TABLE.as_ptr().cast::<u8>(),
+            size_of_val(&IDENTIFIER_TABLE),
+        )
+    }
 }
 
 /// Checks if a codepoint is a unicode identifier, defined by
 /// Unicode Standard Annex #31.
 #[inline]
 pub fn is_identifier(cp: &[char]) -> bool {
-    if cp.len() == 0 {
+    if cp.is_empty() {
         return false;
     }
 
@@ -44,7 +68,7 @@ pub fn is_identifier(cp: &[char]) -> bool {
         return false;
     }
 
-    for (i, c) in cp.into_iter().enumerate() {
+    for (i, c) in cp.iter().enumerate() {
         if !unicode_identifier_class(*c).is_continue() {
             // the two special characters are only allowed in the
             // middle, not the end.
@@ -57,19 +81,18 @@ pub fn is_identifier(cp: &[char]) -> bool {
     true
 }
 
+/// Returns the [UnicodeIdentifierClass] of the first `char` in `s`, or `None`
+/// if `s` is empty.
+#[inline]
+pub fn first_char_class(s: &str) -> Option<UnicodeIdentifierClass> {
+    s.chars().next().map(unicode_identifier_class)
+}
+
 /// Checks if a given string is a unicode identifier, defined by Unicode
 /// Standard Annex #31.
 #[inline]
 pub fn str_is_identifier(s: &str) -> bool {
-    if s.len() == 0 {
-        return false;
-    }
-
-    if !unicode_identifier_class(
-        s.chars().nth(0).expect("we already checked that len > 0"),
-    )
-    .is_start()
-    {
+    if !first_char_class(s).is_some_and(|x| x.is_start()) {
         return false;
     }
 
@@ -91,44 +114,16 @@ pub fn str_is_identifier(s: &str) -> bool {
 mod tests {
     use super::*;
     use proptest::prelude::*;
-    use std::{fs::File, path::PathBuf, sync::OnceLock};
-
-    const MAX_SCALAR: usize = 0x110000;
+    use std::sync::OnceLock;
+    use unicode_id_trie_rle_derived_core_properties::{
+        IdentifierProperties, parse_default, to_identifier_bits,
+    };
 
     fn derived_identifier_table() -> &'static [u8] {
         static TABLE: OnceLock<Box<[u8]>> = OnceLock::new();
-        TABLE
-            .get_or_init(|| {
-                let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-                let derived_path =
-                    manifest_dir.join("../DerivedCoreProperties.txt");
-                let file = File::open(&derived_path).unwrap_or_else(|err| {
-                    panic!("failed to open {}: {err}", derived_path.display())
-                });
-
-                let parsed =
-                    unicode_id_trie_rle_derived_core_properties::parse(file)
-                        .unwrap_or_else(|err| {
-                            panic!("failed to parse derived data: {err}")
-                        });
-                let mut table = vec![0u8; MAX_SCALAR];
-                for (ch, props) in parsed {
-                    let mut bits = 0u8;
-                    for prop in props {
-                        if prop.contains("XID_Start") {
-                            bits |= IDENTIFIER_START;
-                        }
-                        if prop.contains("XID_Continue") {
-                            bits |= IDENTIFIER_CONTINUE;
-                        }
-                    }
-
-                    table[ch as usize] = bits;
-                }
-
-                table.into_boxed_slice()
-            })
-            .as_ref()
+        TABLE.get_or_init(|| {
+            to_identifier_bits(parse_default(), IdentifierProperties::Xid)
+        })
     }
 
     #[test]
@@ -160,6 +155,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn dense_table_matches_unicode_identifier_class() {
+        let table = dense_table();
+        assert_eq!(table.len(), (0x10ffff + 1) / 4);
+        for ch in (0..=0x10ffff).filter_map(char::from_u32) {
+            let cp = ch as usize;
+            let bits = (table[cp >> 2] >> ((cp & 3) * 2)) & 3;
+            assert_eq!(bits, unicode_identifier_class(ch).0, "U+{cp:04X}");
+        }
+    }
+
     proptest! {
         #[test]
         fn unicode_identifier_class_proptest(cp in any::<char>()) {
@@ -0,0 +1,56 @@
+// Compares unicode-id-trie-rle's is_identifier, which checks all-ASCII slices
+// with only the ASCII table, against classifying every char of the same slice
+// with unicode_identifier_class. Uses the 100% ASCII corpus, already decoded
+// into chars, along with the 90% ASCII corpus to show what checking for the
+// fast path costs when it can't be taken.
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use unicode_id_trie_rle::{is_identifier, unicode_identifier_class};
+
+mod common;
+
+use common::load_cases;
+
+/// The per char loop `is_identifier` runs for slices which aren't all ASCII.
+fn general_is_identifier(cp: &[char]) -> bool {
+    if !cp
+        .first()
+        .is_some_and(|&c| unicode_identifier_class(c).is_start())
+    {
+        return false;
+    }
+
+    for (i, c) in cp.iter().enumerate() {
+        if !unicode_identifier_class(*c).is_continue()
+            && ((*c != '\u{200c}' && *c != '\u{200d}') || i + 1 == cp.len())
+        {
+            return false;
+        }
+    }
+
+    true
+}
+
+fn benchmark(c: &mut Criterion) {
+    for percent in [0, 10] {
+        let cases = load_cases(percent);
+        let mut group =
+            c.benchmark_group(format!("ascii slice/{}% ascii", 100 - percent));
+        for case in &cases {
+            let chars: Vec<char> = case.input.chars().collect();
+            group.bench_with_input(
+                BenchmarkId::new("is_identifier", case.len),
+                &chars,
+                |b, i| b.iter(|| is_identifier(i)),
+            );
+            group.bench_with_input(
+                BenchmarkId::new("general", case.len),
+                &chars,
+                |b, i| b.iter(|| general_is_identifier(i)),
+            );
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, benchmark);
+criterion_main!(benches);
@@ -0,0 +1,55 @@
+// Compares classifying every character of single script astral text through a
+// ClassifierCache against plain unicode_identifier_class calls. The corpus is
+// built from the Adlam block (U+1E900..U+1E95F), so after the first character
+// every lookup hits the cache.
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use unicode_id_trie_rle::{ClassifierCache, unicode_identifier_class};
+
+const LENGTHS: [usize; 3] = [32, 128, 512];
+const ADLAM_LETTERS: u32 = 0x1e900;
+const ADLAM_LETTER_COUNT: u32 = 0x44;
+
+/// Returns `len` Adlam letters picked with a fixed linear congruential
+/// generator, so every run uses the same input.
+fn adlam_text(len: usize) -> String {
+    let mut state: u32 = 0x2545_f491;
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let letter = (state >> 16) % ADLAM_LETTER_COUNT;
+            char::from_u32(ADLAM_LETTERS + letter).expect("Adlam letter")
+        })
+        .collect()
+}
+
+fn benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("classifier cache");
+    for len in LENGTHS {
+        let input = adlam_text(len);
+        group.bench_with_input(
+            BenchmarkId::new("uncached", len),
+            &input,
+            |b, i| {
+                b.iter(|| {
+                    i.chars()
+                        .filter(|&c| unicode_identifier_class(c).is_start())
+                        .count()
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("cached", len),
+            &input,
+            |b, i| {
+                b.iter(|| {
+                    let mut cache = ClassifierCache::new();
+                    i.chars().filter(|&c| cache.classify(c).is_start()).count()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark);
+criterion_main!(benches);
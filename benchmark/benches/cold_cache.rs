@@ -0,0 +1,69 @@
+// Measures a single `str_is_identifier` call with cold caches, which is closer
+// to a short-lived tool validating one identifier and exiting than the hot
+// loops in the other benches. Before every call the input is copied to a fresh
+// allocation and a buffer larger than most last level caches is written to
+// evict the tables. This is as much as can be done portably; it doesn't flush
+// the TLB or branch predictor.
+use criterion::{
+    BatchSize, BenchmarkId, Criterion, black_box, criterion_group,
+    criterion_main,
+};
+
+mod common;
+
+use common::load_cases;
+
+const EVICTION_BYTES: usize = 64 * 1024 * 1024;
+const CACHE_LINE: usize = 64;
+
+type IsIdentifier = fn(&str) -> bool;
+
+const IMPLEMENTATIONS: [(&str, IsIdentifier); 6] = [
+    ("adaptive", adaptive::str_is_identifier),
+    ("baseline", baseline::str_is_identifier),
+    ("delta-encoded", delta_encoded::str_is_identifier),
+    ("run-indexed", run_indexed::str_is_identifier),
+    (
+        "unicode-id-start",
+        unicode_id_start_harness::str_is_identifier,
+    ),
+    (
+        "unicode-id-trie-rle",
+        unicode_id_trie_rle::str_is_identifier,
+    ),
+];
+
+fn evict(buffer: &mut [u8]) {
+    for line in buffer.chunks_mut(CACHE_LINE) {
+        line[0] = line[0].wrapping_add(1);
+    }
+    black_box(buffer);
+}
+
+fn benchmark(c: &mut Criterion) {
+    let cases = load_cases(100);
+    let mut buffer = vec![0u8; EVICTION_BYTES];
+    let mut group = c.benchmark_group("cold cache");
+    for case in &cases {
+        for (name, is_identifier) in IMPLEMENTATIONS {
+            group.bench_with_input(
+                BenchmarkId::new(name, case.len),
+                &case.input,
+                |b, i| {
+                    b.iter_batched(
+                        || {
+                            evict(&mut buffer);
+                            i.clone()
+                        },
+                        |input| is_identifier(&input),
+                        BatchSize::PerIteration,
+                    )
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark);
+criterion_main!(benches);
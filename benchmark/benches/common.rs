@@ -4,12 +4,14 @@ use criterion::{BenchmarkId, Criterion};
 
 const LENGTHS: [usize; 3] = [32, 128, 512];
 
-struct TestCase {
-    len: usize,
-    input: String,
+pub struct TestCase {
+    pub len: usize,
+    pub input: String,
 }
 
-fn load_cases(percent: u8) -> Vec<TestCase> {
+/// Loads the corpus for each of [LENGTHS] where `percent` percent of the
+/// characters are non-ASCII.
+pub fn load_cases(percent: u8) -> Vec<TestCase> {
     let mut cases = Vec::with_capacity(LENGTHS.len());
     for len in LENGTHS {
         let ascii_percent = 100 - percent;
@@ -48,6 +50,11 @@ pub fn bench_full_suite(c: &mut Criterion, percent: u8) {
     let cases = load_cases(percent);
     let mut group = c.benchmark_group(label);
     for case in &cases {
+        group.bench_with_input(
+            BenchmarkId::new("adaptive", case.len),
+            &case.input,
+            |b, i| b.iter(|| adaptive::str_is_identifier(i)),
+        );
         group.bench_with_input(
             BenchmarkId::new("baseline", case.len),
             &case.input,
@@ -0,0 +1,29 @@
+// Measures what the `unsafe` unchecked run table loads in `run-indexed` buy
+// over bounds checked loads. Uses the 0% ASCII corpus so every character goes
+// through the run table.
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+mod common;
+
+use common::load_cases;
+
+fn benchmark(c: &mut Criterion) {
+    let cases = load_cases(100);
+    let mut group = c.benchmark_group("run-indexed safety");
+    for case in &cases {
+        group.bench_with_input(
+            BenchmarkId::new("unchecked", case.len),
+            &case.input,
+            |b, i| b.iter(|| run_indexed::str_is_identifier(i)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("checked", case.len),
+            &case.input,
+            |b, i| b.iter(|| run_indexed::str_is_identifier_checked(i)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark);
+criterion_main!(benches);
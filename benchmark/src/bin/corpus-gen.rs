@@ -0,0 +1,131 @@
+//! Generates a benchmark corpus file: a single identifier of a given length in
+//! characters with a given percentage of ASCII characters, in the format
+//! `load_cases` in `benches/common.rs` reads.
+//!
+//! CLI usage:
+//! - `cargo run -p benchmark --bin corpus-gen -- <length> <ascii-percent>`
+//!
+//! The identifier is written to stdout without a trailing newline, so adding
+//! a case is `cargo run -p benchmark --bin corpus-gen -- 64 75 >
+//! benchmark/corpus/ascii-75/len64.txt`.
+//!
+//! There's no randomness involved, so the same arguments always give the same
+//! file, and every committed corpus can be regenerated this way. The
+//! identifier is built from three parts:
+//!
+//! 1. `a`, or `π` if there are no ASCII characters, so it starts with a start
+//!    character.
+//! 2. The non-ASCII characters, cycling through `πλЖ中अβδ`. There are
+//!    `round(length * (100 - ascii-percent) / 100)` of them, counting a
+//!    leading `π`.
+//! 3. The remaining characters, cycling through `abc_01`.
+
+use std::{
+    env,
+    io::{self, Write},
+};
+
+const NON_ASCII: &[char] = &['π', 'λ', 'Ж', '中', 'अ', 'β', 'δ'];
+const ASCII: &[char] = &['a', 'b', 'c', '_', '0', '1'];
+
+const USAGE: &str = "Usage: corpus-gen <length> <ascii-percent>";
+
+fn main() {
+    let (len, ascii_percent) = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    let corpus = generate(len, ascii_percent);
+    if let Err(err) = io::stdout().lock().write_all(corpus.as_bytes()) {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn parse_args() -> Result<(usize, u8), String> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|x| x == "--help" || x == "-h") {
+        println!("{USAGE}");
+        std::process::exit(0);
+    }
+    let [len, ascii_percent] = args.as_slice() else {
+        return Err("expected a length and an ASCII percentage".to_string());
+    };
+
+    let len = match len.parse() {
+        Ok(0) | Err(_) => return Err(format!("invalid length {len:?}")),
+        Ok(len) => len,
+    };
+    let ascii_percent = match ascii_percent.parse() {
+        Ok(x) if x <= 100 => x,
+        _ => {
+            return Err(format!("invalid ASCII percentage {ascii_percent:?}"));
+        }
+    };
+    Ok((len, ascii_percent))
+}
+
+/// Returns the corpus of `len` characters, about `ascii_percent`% of which are
+/// ASCII, as described in the module documentation.
+fn generate(len: usize, ascii_percent: u8) -> String {
+    // rounded the same way `load_cases` rounds its target.
+    let non_ascii = (len * (100 - ascii_percent as usize) + 50) / 100;
+    let (first, non_ascii) = if non_ascii == len {
+        (NON_ASCII[0], non_ascii - 1)
+    } else {
+        (ASCII[0], non_ascii)
+    };
+
+    let mut corpus = String::new();
+    corpus.push(first);
+    corpus.extend(NON_ASCII.iter().cycle().take(non_ascii));
+    corpus.extend(ASCII.iter().cycle().take(len - 1 - non_ascii));
+    corpus
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn regenerates_committed_corpora() {
+        let corpus = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("corpus");
+        let mut count = 0;
+        for dir in fs::read_dir(&corpus).unwrap() {
+            let dir = dir.unwrap().path();
+            let name = dir.file_name().unwrap().to_str().unwrap();
+            let ascii_percent = name["ascii-".len()..].parse().unwrap();
+            for file in fs::read_dir(&dir).unwrap() {
+                let file = file.unwrap().path();
+                let name = file.file_stem().unwrap().to_str().unwrap();
+                let len = name["len".len()..].parse().unwrap();
+                assert_eq!(
+                    generate(len, ascii_percent),
+                    fs::read_to_string(&file).unwrap(),
+                    "{file:?}"
+                );
+                count += 1;
+            }
+        }
+        assert!(count > 0);
+    }
+
+    #[test]
+    fn corpora_are_identifiers() {
+        for len in [1, 2, 7, 32, 100] {
+            for ascii_percent in [0, 1, 25, 50, 99, 100] {
+                let corpus = generate(len, ascii_percent);
+                assert_eq!(corpus.chars().count(), len);
+                assert!(
+                    unicode_id_trie_rle::str_is_identifier(&corpus),
+                    "{len} {ascii_percent}: {corpus:?}"
+                );
+            }
+        }
+    }
+}
@@ -1,5 +1,4 @@
 use std::{
-    collections::BTreeMap,
     env,
     error::Error,
     fs::File,
@@ -7,6 +6,10 @@ use std::{
     path::PathBuf,
 };
 
+use unicode_id_trie_rle_derived_core_properties::{
+    IdentifierProperties, to_identifier_bits,
+};
+
 const MAX_CODEPOINT: u32 = 0x10ffff;
 const BYTES_PER_LINE: usize = 12;
 
@@ -18,7 +21,7 @@ static mut EMIT_BUFFER_SIZE: u8 = 0;
 /// `emit_bits(5, x)` then `emit_bits(3, y)` results in the 5 least significant
 /// bits being `xxxxx` and the 3 most significant bits being `yyy`.
 fn emit_bits(n: u8, mut value: u64, buffer: &mut Vec<u8>) {
-    if n <= 0 || n > 8 {
+    if n == 0 || n > 8 {
         panic!("emit_bits called with out of range value. N must be [1, 8]");
     }
 
@@ -82,27 +85,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     let file = File::open(&derived)?;
     let parsed = unicode_id_trie_rle_derived_core_properties::parse(file)?;
 
-    // Convert the parsed file into a map from codepoint => 2 bit value
-    let parsed = parsed
-        .iter()
-        .filter(|(_, v)| v.contains("XID_Start") || v.contains("XID_Continue"))
-        .map(|(k, v)| {
-            (
-                *k,
-                v.iter()
-                    .map(|x| {
-                        if x.contains("XID_Start") {
-                            1 << 0
-                        } else if x.contains("XID_Continue") {
-                            1 << 1
-                        } else {
-                            0
-                        }
-                    })
-                    .fold(0, |acc, x| acc | x),
-            )
-        })
-        .collect::<BTreeMap<u32, i32>>();
+    let table = to_identifier_bits(&parsed, IdentifierProperties::Xid);
 
     let mut buffer = Vec::with_capacity(1 << 15);
 
@@ -111,13 +94,12 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut run_end: u32 = 0;
     let mut run_value: u8 = 0;
 
-    for (cp_char, value_bits) in parsed.into_iter() {
-        let cp = cp_char as u32;
-        if cp >= 0x100000 || cp < 0x80 {
+    for cp in 0x80..0x100000 {
+        let value = table[cp as usize];
+        if value == 0 {
             continue;
         }
 
-        let value = (value_bits & 3) as u8;
         if let Some(start) = run_start {
             if cp == run_end && value == run_value {
                 run_end += 1;
@@ -164,12 +146,12 @@ fn main() -> Result<(), Box<dyn Error>> {
         "pub(crate) static IDENTIFIER_TABLE: [u8; {total_len}] = ["
     )?;
 
-    for idx in 0..bytes {
+    for (idx, byte) in buffer.iter().enumerate() {
         if idx % BYTES_PER_LINE == 0 {
             write!(writer, "\t")?;
         }
 
-        write!(writer, "0x{:02x},", buffer[idx])?;
+        write!(writer, "0x{byte:02x},")?;
 
         if idx % BYTES_PER_LINE == BYTES_PER_LINE - 1 || idx + 1 == bytes {
             writeln!(writer)?;
@@ -1,9 +1,14 @@
+#![cfg_attr(not(test), no_std)]
+
 const IDENTIFIER_OTHER: u8 = 0;
 const IDENTIFIER_START: u8 = 1;
 const IDENTIFIER_CONTINUE: u8 = 2;
 
 include!(concat!(env!("OUT_DIR"), "/table.rs"));
 
+/// The number of bytes of static storage used by the lookup table.
+pub const TABLE_BYTES: usize = size_of_val(&IDENTIFIER_TABLE);
+
 pub struct UnicodeIdentifierClass(u8);
 
 impl UnicodeIdentifierClass {
@@ -130,11 +135,46 @@ pub fn unicode_identifier_class(cp: char) -> UnicodeIdentifierClass {
     UnicodeIdentifierClass(IDENTIFIER_OTHER)
 }
 
+/// Decodes every run in the table, in order, as `(start, len, value)`, where
+/// `value` has the same bits as a [UnicodeIdentifierClass].
+///
+/// Only runs of codepoints from `U+0080` up with a nonzero class are stored,
+/// since [unicode_identifier_class] handles ASCII itself, so every codepoint
+/// between runs is neither a start nor a continue character.
+pub fn runs() -> impl Iterator<Item = (u32, u32, u8)> {
+    let mut reader = BitReader {
+        buffer: IDENTIFIER_TABLE,
+        current: 0,
+        current_bitpos: 0,
+    };
+    let mut index = 0;
+    let mut done = false;
+    core::iter::from_fn(move || {
+        if done || reader.is_at_end() {
+            return None;
+        }
+
+        index += reader.read_leb128();
+        let run_len = reader.read_leb128();
+        let run_val = reader.read_bits(2);
+        // `build.rs` ends the table with a sentinel run of other codepoints,
+        // followed by padding.
+        if run_val == IDENTIFIER_OTHER {
+            done = true;
+            return None;
+        }
+
+        let start = index;
+        index += run_len;
+        Some((start, run_len, run_val))
+    })
+}
+
 /// Checks if a codepoint is a unicode identifier, defined by
 /// Unicode Standard Annex #31.
 #[inline]
 pub fn is_identifier(cp: &[char]) -> bool {
-    if cp.len() == 0 {
+    if cp.is_empty() {
         return false;
     }
 
@@ -142,7 +182,7 @@ pub fn is_identifier(cp: &[char]) -> bool {
         return false;
     }
 
-    for (i, c) in cp.into_iter().enumerate() {
+    for (i, c) in cp.iter().enumerate() {
         if !unicode_identifier_class(*c).is_continue() {
             // the two special characters are only allowed in the
             // middle, not the end.
@@ -155,28 +195,27 @@ pub fn is_identifier(cp: &[char]) -> bool {
     true
 }
 
+/// Returns the [UnicodeIdentifierClass] of the first `char` in `s`, or `None`
+/// if `s` is empty.
+#[inline]
+pub fn first_char_class(s: &str) -> Option<UnicodeIdentifierClass> {
+    s.chars().next().map(unicode_identifier_class)
+}
+
 /// Checks if a given string is a unicode identifier, defined by Unicode
 /// Standard Annex #31.
 #[inline]
 pub fn str_is_identifier(s: &str) -> bool {
-    if s.len() == 0 {
+    if !first_char_class(s).is_some_and(|x| x.is_start()) {
         return false;
     }
 
-    if !unicode_identifier_class(
-        s.chars().nth(0).expect("we already checked that len > 0"),
-    )
-    .is_start()
-    {
-        return false;
-    }
-
-    let cp = s.chars().collect::<Vec<_>>();
-    for (i, c) in cp.iter().enumerate() {
-        if !unicode_identifier_class(*c).is_continue() {
+    let mut iter = s.chars().peekable();
+    while let Some(c) = iter.next() {
+        if !unicode_identifier_class(c).is_continue() {
             // the two special characters are only allowed in the
             // middle, not the end.
-            if (*c != '\u{200c}' && *c != '\u{200d}') || i + 1 == cp.len() {
+            if (c != '\u{200c}' && c != '\u{200d}') || iter.peek().is_none() {
                 return false;
             }
         }
@@ -189,44 +228,16 @@ pub fn str_is_identifier(s: &str) -> bool {
 mod tests {
     use super::*;
     use proptest::prelude::*;
-    use std::{fs::File, path::PathBuf, sync::OnceLock};
-
-    const MAX_SCALAR: usize = 0x110000;
+    use std::sync::OnceLock;
+    use unicode_id_trie_rle_derived_core_properties::{
+        IdentifierProperties, parse_default, to_identifier_bits,
+    };
 
     fn derived_identifier_table() -> &'static [u8] {
         static TABLE: OnceLock<Box<[u8]>> = OnceLock::new();
-        TABLE
-            .get_or_init(|| {
-                let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-                let derived_path =
-                    manifest_dir.join("../DerivedCoreProperties.txt");
-                let file = File::open(&derived_path).unwrap_or_else(|err| {
-                    panic!("failed to open {}: {err}", derived_path.display())
-                });
-
-                let parsed =
-                    unicode_id_trie_rle_derived_core_properties::parse(file)
-                        .unwrap_or_else(|err| {
-                            panic!("failed to parse derived data: {err}")
-                        });
-                let mut table = vec![0u8; MAX_SCALAR];
-                for (ch, props) in parsed {
-                    let mut bits = 0u8;
-                    for prop in props {
-                        if prop.contains("XID_Start") {
-                            bits |= IDENTIFIER_START;
-                        }
-                        if prop.contains("XID_Continue") {
-                            bits |= IDENTIFIER_CONTINUE;
-                        }
-                    }
-
-                    table[ch as usize] = bits;
-                }
-
-                table.into_boxed_slice()
-            })
-            .as_ref()
+        TABLE.get_or_init(|| {
+            to_identifier_bits(parse_default(), IdentifierProperties::Xid)
+        })
     }
 
     #[test]
@@ -258,6 +269,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn runs_reconstruct_unicode_identifier_class() {
+        let mut expected = vec![IDENTIFIER_OTHER; 0x110000];
+        let mut end = 0;
+        for (start, len, value) in runs() {
+            assert!(start >= end.max(0x80), "run at U+{start:04X} overlaps");
+            assert!(len > 0 && value != IDENTIFIER_OTHER);
+            expected[start as usize..(start + len) as usize].fill(value);
+            end = start + len;
+        }
+
+        // unicode_identifier_class is checked against the derived data for
+        // every codepoint above, so compare the runs to that and only call it
+        // around the edges of each run, since every lookup decodes the table
+        // from the start.
+        let table = derived_identifier_table();
+        assert_eq!(expected[0x80..], table[0x80..]);
+        for (start, len, _) in runs() {
+            for cp in [start - 1, start, start + len - 1, start + len] {
+                let Some(ch) = char::from_u32(cp) else {
+                    continue;
+                };
+                assert_eq!(
+                    unicode_identifier_class(ch).0,
+                    expected[cp as usize],
+                    "mismatch at U+{cp:04X}"
+                );
+            }
+        }
+    }
+
     proptest! {
         #[test]
         fn unicode_identifier_class_proptest(cp in any::<char>()) {
@@ -0,0 +1,46 @@
+use std::{
+    env, fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=build.rs");
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?);
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+
+    // Hashes the source for `SOURCE_HASH`, which dependents' build scripts
+    // use to tell when the parser changed without a version bump.
+    let src = manifest_dir.join("src");
+    println!("cargo:rerun-if-changed={}", src.display());
+    let mut hasher = DefaultHasher::new();
+    hash_dir(&src, &src, &mut hasher)?;
+    fs::write(
+        out_dir.join("source_hash.rs"),
+        format!("{:#018x}", hasher.finish()),
+    )?;
+
+    Ok(())
+}
+
+/// Hashes the path relative to `root` and the contents of every file under
+/// `dir`, in sorted order so the hash doesn't depend on the filesystem.
+fn hash_dir(
+    root: &Path,
+    dir: &Path,
+    hasher: &mut DefaultHasher,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>, std::io::Error>>()?;
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            hash_dir(root, &path, hasher)?;
+        } else {
+            path.strip_prefix(root)?.hash(hasher);
+            fs::read(&path)?.hash(hasher);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,46 @@
+//! Prints every codepoint where `ID_Start` and `XID_Start`, or `ID_Continue`
+//! and `XID_Continue`, disagree, along with which of the properties it has.
+//!
+//! ```text
+//! cargo run -p unicode-id-trie-rle-derived-core-properties --example id-xid-diff
+//! ```
+
+use unicode_id_trie_rle_derived_core_properties::{
+    IDENTIFIER_CONTINUE, IDENTIFIER_START, IdentifierProperties,
+    parse_default, to_identifier_bits,
+};
+
+fn main() {
+    let parsed = parse_default();
+    let id = to_identifier_bits(parsed, IdentifierProperties::Id);
+    let xid = to_identifier_bits(parsed, IdentifierProperties::Xid);
+
+    let describe = |bits: u8, prefix: &str| {
+        let mut names = Vec::new();
+        if bits & IDENTIFIER_START != 0 {
+            names.push(format!("{prefix}_Start"));
+        }
+        if bits & IDENTIFIER_CONTINUE != 0 {
+            names.push(format!("{prefix}_Continue"));
+        }
+        if names.is_empty() {
+            names.push(format!("neither {prefix} property"));
+        }
+        names.join(" + ")
+    };
+
+    let mut count = 0;
+    for (cp, (&id, &xid)) in id.iter().zip(xid.iter()).enumerate() {
+        if id == xid {
+            continue;
+        }
+
+        count += 1;
+        println!(
+            "U+{cp:04X}: {} but {}",
+            describe(id, "ID"),
+            describe(xid, "XID")
+        );
+    }
+    println!("{count} codepoints differ");
+}
@@ -0,0 +1,61 @@
+//! Prints a histogram of the lengths of the runs of codepoints with the same
+//! `XID_Start` and `XID_Continue` values, which is what decides how well each
+//! encoding does: the trie's leaves and `delta-encoded` store one entry per
+//! run, and the trie deduplicates whole blocks of short runs, while long runs
+//! are nearly free in `delta-encoded`.
+//!
+//! Each bucket is 4 times as wide as the one before it, and runs with neither
+//! property are counted separately, since the encodings only spend space on
+//! their boundaries.
+//!
+//! ```text
+//! cargo run -p unicode-id-trie-rle-derived-core-properties --example run-lengths
+//! ```
+
+use unicode_id_trie_rle_derived_core_properties::{
+    IdentifierProperties, TABLE_LEN, identifier_runs, parse_default,
+    to_identifier_bits,
+};
+
+fn main() {
+    let table = to_identifier_bits(parse_default(), IdentifierProperties::Xid);
+
+    // the bucket of a run of length `len` is the one with `lo <= len <= hi`,
+    // for bounds 1, 2..=4, 5..=16, and so on up to the whole table.
+    let mut bounds = vec![(1, 1)];
+    while bounds.last().unwrap().1 < TABLE_LEN {
+        let hi = bounds.last().unwrap().1;
+        bounds.push((hi + 1, hi * 4));
+    }
+
+    let mut identifier = vec![0usize; bounds.len()];
+    let mut other = vec![0usize; bounds.len()];
+    for (range, value) in identifier_runs(&table) {
+        let len = range.len();
+        let bucket = bounds.iter().position(|&(_, hi)| len <= hi).unwrap();
+        if value == 0 {
+            other[bucket] += 1;
+        } else {
+            identifier[bucket] += 1;
+        }
+    }
+
+    println!("{:>17} {:>10} {:>10}", "length", "identifier", "other");
+    for (i, &(lo, hi)) in bounds.iter().enumerate() {
+        if identifier[i] == 0 && other[i] == 0 {
+            continue;
+        }
+        let label = if lo == hi {
+            format!("{lo}")
+        } else {
+            format!("{lo}-{hi}")
+        };
+        println!("{label:>17} {:>10} {:>10}", identifier[i], other[i]);
+    }
+    println!(
+        "{:>17} {:>10} {:>10}",
+        "total",
+        identifier.iter().sum::<usize>(),
+        other.iter().sum::<usize>()
+    );
+}
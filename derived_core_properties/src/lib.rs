@@ -6,9 +6,15 @@
 //! and makes no guarantees about stability or correctness.
 
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashSet},
+    env,
+    fs::File,
     io::{self, BufRead, BufReader},
     num::ParseIntError,
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::OnceLock,
 };
 
 use thiserror::Error;
@@ -23,18 +29,493 @@ pub enum Error {
     IntParseRangeError(#[from] ParseIntError),
     #[error("one of the codepoints was outside the valid unicode range")]
     InvalidCodepoint,
+    #[error("range U+{start:04X}..U+{end:04X} ends before it starts")]
+    InvalidRange { start: u32, end: u32 },
     #[error("missing ';' delimiter in line: {0}")]
     MissingDelimiter(String),
+    #[error("unknown override class: {0}")]
+    UnknownOverride(String),
+    #[error("unknown identifier property: {0}")]
+    UnknownProperty(String),
+    #[error("U+{0:04X} is a start character but not a continue character")]
+    StartWithoutContinue(u32),
+    #[error("no {0} codepoints found — wrong data file?")]
+    NoStartCodepoints(&'static str),
+    #[error("unknown script: {0}")]
+    UnknownScript(String),
+    #[error(
+        "{what} don't fit in a u16 index ({index} > {}), the generated tables \
+         need to be widened to u32",
+        u16::MAX
+    )]
+    IndexOverflow { what: &'static str, index: usize },
+    #[cfg(feature = "ucd-xml")]
+    #[error("there was an error parsing the XML: {0}")]
+    XmlError(#[from] quick_xml::Error),
+}
+
+/// The version of this crate, for build scripts which cache the tables they
+/// generate from what it parses.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A hash of this crate's source. Like [VERSION], this is for build scripts
+/// which cache generated tables, since a path dependency can change without
+/// its version changing.
+pub const SOURCE_HASH: u64 =
+    include!(concat!(env!("OUT_DIR"), "/source_hash.rs"));
+
+/// The bit set in an identifier table entry for `*_Start` codepoints.
+pub const IDENTIFIER_START: u8 = 1;
+/// The bit set in an identifier table entry for `*_Continue` codepoints.
+pub const IDENTIFIER_CONTINUE: u8 = 2;
+
+/// The number of entries in a table indexed by codepoint, one past
+/// `U+10FFFF`.
+pub const TABLE_LEN: usize = 0x110000;
+
+/// One of the properties which make up an identifier table entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IdentifierProperty {
+    IdStart,
+    IdContinue,
+    XidStart,
+    XidContinue,
+}
+
+impl IdentifierProperty {
+    /// Returns the canonical name of the property, as used in
+    /// `DerivedCoreProperties.txt`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::IdStart => "ID_Start",
+            Self::IdContinue => "ID_Continue",
+            Self::XidStart => "XID_Start",
+            Self::XidContinue => "XID_Continue",
+        }
+    }
+
+    /// Returns the bit this property sets in an identifier table entry,
+    /// either [IDENTIFIER_START] or [IDENTIFIER_CONTINUE].
+    pub fn bit(self) -> u8 {
+        match self {
+            Self::IdStart | Self::XidStart => IDENTIFIER_START,
+            Self::IdContinue | Self::XidContinue => IDENTIFIER_CONTINUE,
+        }
+    }
+}
+
+impl FromStr for IdentifierProperty {
+    type Err = Error;
+
+    /// Parses the canonical name of a property, such as `XID_Start`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ID_Start" => Ok(Self::IdStart),
+            "ID_Continue" => Ok(Self::IdContinue),
+            "XID_Start" => Ok(Self::XidStart),
+            "XID_Continue" => Ok(Self::XidContinue),
+            _ => Err(Error::UnknownProperty(s.to_string())),
+        }
+    }
+}
+
+/// Which pair of identifier properties [to_identifier_bits] reads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdentifierProperties {
+    /// `ID_Start` and `ID_Continue`.
+    Id,
+    /// `XID_Start` and `XID_Continue`, which are `ID_Start` and `ID_Continue`
+    /// modified to be closed under NFKC normalization.
+    Xid,
+}
+
+/// Every property name [parse] can produce from `DerivedCoreProperties.txt`,
+/// indexed by its id. `InCB` is the only property with a value, and the
+/// parser keeps the value as part of the name.
+const PROPERTY_NAMES: &[&str] = &[
+    "Alphabetic",
+    "Case_Ignorable",
+    "Cased",
+    "Changes_When_Casefolded",
+    "Changes_When_Casemapped",
+    "Changes_When_Lowercased",
+    "Changes_When_Titlecased",
+    "Changes_When_Uppercased",
+    "Default_Ignorable_Code_Point",
+    "Grapheme_Base",
+    "Grapheme_Extend",
+    "Grapheme_Link",
+    "ID_Continue",
+    "ID_Start",
+    "InCB; Consonant",
+    "InCB; Extend",
+    "InCB; Linker",
+    "Lowercase",
+    "Math",
+    "Uppercase",
+    "XID_Continue",
+    "XID_Start",
+];
+
+/// Returns the numeric id of a property name as produced by [parse], or
+/// [None] if it isn't a `DerivedCoreProperties.txt` property.
+///
+/// Ids are dense, starting at 0, so they can be used as bit indices. They are
+/// stable within a version of this crate, but may change between versions.
+pub fn property_id(name: &str) -> Option<u16> {
+    PROPERTY_NAMES
+        .iter()
+        .position(|&x| x == name)
+        .map(|id| id as u16)
+}
+
+/// Returns the property name for an id returned by [property_id].
+pub fn property_name(id: u16) -> Option<&'static str> {
+    PROPERTY_NAMES.get(id as usize).copied()
 }
 
 /// Reads in data from a `DerivedCoreProperties.txt` file into a [BTreeMap]
 /// from each codepoint to a [HashSet] of that codepoint's properties.
+///
+/// Any amount of whitespace, including tabs, is accepted around the
+/// codepoints, the `..` between the ends of a range and the `;`.
 pub fn parse<R: io::Read>(
     reader: R,
 ) -> Result<BTreeMap<u32, HashSet<String>>, Error> {
+    let mut result = BTreeMap::new();
+    parse_into(reader, &mut result)?;
+    Ok(result)
+}
+
+/// Like [parse], but reads from a string which is already in memory, such as
+/// one from [include_str], without copying each line into a buffer first.
+pub fn parse_str(s: &str) -> Result<BTreeMap<u32, HashSet<String>>, Error> {
+    let mut result = BTreeMap::new();
+    for line in s.lines() {
+        parse_line(line, &mut result)?;
+    }
+    Ok(result)
+}
+
+/// Like [parse], but stores each codepoint's properties in a [BTreeSet], so
+/// iterating over the result is in the same order on every run. Prefer this
+/// when serializing the result, such as for snapshot tests.
+pub fn parse_sorted<R: io::Read>(
+    reader: R,
+) -> Result<BTreeMap<u32, BTreeSet<String>>, Error> {
+    Ok(parse(reader)?
+        .into_iter()
+        .map(|(cp, properties)| (cp, properties.into_iter().collect()))
+        .collect())
+}
+
+/// Returns the path of the file [parse_default] reads. This is the value of
+/// the `UNICODE_DATA_PATH` environment variable at runtime if it's set, and
+/// otherwise the `DerivedCoreProperties.txt` at the root of the repository
+/// this crate was built from, which only exists when building from a checkout
+/// of the repository.
+pub fn default_data_path() -> PathBuf {
+    match env::var_os("UNICODE_DATA_PATH") {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../DerivedCoreProperties.txt"),
+    }
+}
+
+/// The `DerivedCoreProperties.txt` at the root of the repository, parsed by
+/// [parse_default] with the `test-data` feature. It isn't part of the
+/// published package, so the feature only builds from a checkout.
+#[cfg(any(test, feature = "test-data"))]
+static EMBEDDED_DATA: &[u8] =
+    include_bytes!("../../DerivedCoreProperties.txt");
+
+/// Parses the file at [default_data_path] the first time it's called, and
+/// returns the same map on every call after that.
+///
+/// With the `test-data` feature, the repository's `DerivedCoreProperties.txt`
+/// is embedded in this crate and parsed instead unless `UNICODE_DATA_PATH` is
+/// set, so no files need to be found at runtime.
+///
+/// # Panics
+///
+/// Panics if the file can't be opened or parsed, since this is meant for
+/// tests and tools which can't do anything without the data.
+pub fn parse_default() -> &'static BTreeMap<u32, HashSet<String>> {
+    static PARSED: OnceLock<BTreeMap<u32, HashSet<String>>> = OnceLock::new();
+    PARSED.get_or_init(|| {
+        #[cfg(any(test, feature = "test-data"))]
+        if env::var_os("UNICODE_DATA_PATH").is_none() {
+            return parse(EMBEDDED_DATA).expect("embedded data is valid");
+        }
+
+        let path = default_data_path();
+        let file = File::open(&path).unwrap_or_else(|err| {
+            panic!("failed to open {}: {err}", path.display())
+        });
+        parse(file).unwrap_or_else(|err| {
+            panic!("failed to parse {}: {err}", path.display())
+        })
+    })
+}
+
+/// Returns the names of the properties `c` has in `map`, as returned by
+/// [parse], in sorted order so the output is the same on every run.
+pub fn properties_of(
+    map: &BTreeMap<u32, HashSet<String>>,
+    c: char,
+) -> impl Iterator<Item = &str> {
+    let mut properties: Vec<&str> = map
+        .get(&(c as u32))
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect();
+    properties.sort_unstable();
+    properties.into_iter()
+}
+
+/// Reads in data from multiple files in the `DerivedCoreProperties.txt`
+/// format, such as `DerivedCoreProperties.txt` and `PropList.txt`, into a
+/// single [BTreeMap] from each codepoint to a [HashSet] of that codepoint's
+/// properties across all of the files.
+pub fn parse_many<R: io::Read>(
+    readers: impl IntoIterator<Item = R>,
+) -> Result<BTreeMap<u32, HashSet<String>>, Error> {
+    let mut result = BTreeMap::new();
+    for reader in readers {
+        parse_into(reader, &mut result)?;
+    }
+    Ok(result)
+}
+
+fn parse_into<R: io::Read>(
+    reader: R,
+    result: &mut BTreeMap<u32, HashSet<String>>,
+) -> Result<(), Error> {
     let mut reader = BufReader::new(reader);
     let mut buf = String::new();
+    loop {
+        buf.clear();
+        if reader.read_line(&mut buf)? == 0 {
+            break;
+        }
+        parse_line(&buf, result)?;
+    }
+
+    Ok(())
+}
+
+/// Adds the properties from a single line of a `DerivedCoreProperties.txt`
+/// file to `result`, skipping comments and blank lines.
+fn parse_line(
+    line: &str,
+    result: &mut BTreeMap<u32, HashSet<String>>,
+) -> Result<(), Error> {
+    let line = match line.find('#') {
+        Some(comment_start) => &line[..comment_start],
+        None => line,
+    };
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return Ok(());
+    }
+
+    let Some((codepoint_range, prop_name)) = trimmed.split_once(';') else {
+        return Err(Error::MissingDelimiter(trimmed.to_string()));
+    };
+
+    let prop_name = prop_name.trim();
+    let (start_range, end_range) = parse_range(codepoint_range)?;
+
+    for cp in start_range..=end_range {
+        if let Some(x) = result.get_mut(&cp) {
+            x.insert(prop_name.to_string());
+        } else {
+            result.insert(cp, HashSet::from_iter([prop_name.to_string()]));
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the identifier properties out of the UCD in its flat XML form,
+/// `ucd.all.flat.xml`, into the same [BTreeMap] from each codepoint to a
+/// [HashSet] of its properties as [parse] produces.
+///
+/// Only the `IDS`, `IDC`, `XIDS` and `XIDC` attributes are read, which become
+/// the `ID_Start`, `ID_Continue`, `XID_Start` and `XID_Continue` properties.
+/// The grouped form of the XML, where elements inherit attributes from their
+/// `<group>`, isn't supported.
+#[cfg(feature = "ucd-xml")]
+pub fn parse_xml<R: io::Read>(
+    reader: R,
+) -> Result<BTreeMap<u32, HashSet<String>>, Error> {
+    use quick_xml::events::Event;
+
+    let mut reader = quick_xml::Reader::from_reader(BufReader::new(reader));
+    let mut buf = Vec::new();
     let mut result: BTreeMap<u32, HashSet<String>> = BTreeMap::new();
+    loop {
+        let element = match reader.read_event_into(&mut buf)? {
+            Event::Eof => break,
+            Event::Start(x) | Event::Empty(x) => x,
+            _ => {
+                buf.clear();
+                continue;
+            }
+        };
+
+        let name = element.local_name();
+        if !matches!(
+            name.as_ref(),
+            b"char" | b"reserved" | b"noncharacter" | b"surrogate"
+        ) {
+            buf.clear();
+            continue;
+        }
+
+        let mut first = None;
+        let mut last = None;
+        let mut props = Vec::new();
+        for attr in element.attributes() {
+            let attr = attr.map_err(quick_xml::Error::from)?;
+            let value = str::from_utf8(&attr.value)
+                .map_err(|_| Error::InvalidCodepoint)?;
+            match (attr.key.local_name().as_ref(), value) {
+                (b"cp", _) => {
+                    first = Some(u32::from_str_radix(value, 16)?);
+                    last = first;
+                }
+                (b"first-cp", _) => {
+                    first = Some(u32::from_str_radix(value, 16)?)
+                }
+                (b"last-cp", _) => {
+                    last = Some(u32::from_str_radix(value, 16)?)
+                }
+                (b"IDS", "Y") => {
+                    props.push(IdentifierProperty::IdStart.name())
+                }
+                (b"IDC", "Y") => {
+                    props.push(IdentifierProperty::IdContinue.name())
+                }
+                (b"XIDS", "Y") => {
+                    props.push(IdentifierProperty::XidStart.name())
+                }
+                (b"XIDC", "Y") => {
+                    props.push(IdentifierProperty::XidContinue.name())
+                }
+                _ => {}
+            }
+        }
+        buf.clear();
+
+        let (Some(first), Some(last)) = (first, last) else {
+            return Err(Error::InvalidCodepoint);
+        };
+        if props.is_empty() {
+            continue;
+        }
+        for cp in first..=last {
+            result
+                .entry(cp)
+                .or_default()
+                .extend(props.iter().map(|x| x.to_string()));
+        }
+    }
+
+    Ok(result)
+}
+
+/// Reads the general category annotations from the trailing comments of a
+/// `DerivedCoreProperties.txt` file into a [BTreeMap] from each codepoint to
+/// its general category, such as `Lu` or `Nl`.
+///
+/// The file annotates every line with the general category of its codepoints,
+/// so every codepoint with at least one derived property is included. Cased
+/// letters may be annotated with `L&` rather than `Lu`, `Ll` or `Lt`. Lines
+/// without an annotation are skipped.
+pub fn parse_general_categories<R: io::Read>(
+    reader: R,
+) -> Result<BTreeMap<u32, String>, Error> {
+    let mut reader = BufReader::new(reader);
+    let mut buf = String::new();
+    let mut result = BTreeMap::new();
+    loop {
+        buf.clear();
+        if reader.read_line(&mut buf)? == 0 {
+            break;
+        }
+
+        let Some((data, comment)) = buf.split_once('#') else {
+            continue;
+        };
+        let data = data.trim();
+        let Some(category) = comment.split_whitespace().next() else {
+            continue;
+        };
+        if data.is_empty() {
+            continue;
+        }
+
+        let Some((codepoint_range, _)) = data.split_once(';') else {
+            return Err(Error::MissingDelimiter(data.to_string()));
+        };
+
+        let (start_range, end_range) = parse_range(codepoint_range)?;
+        for cp in start_range..=end_range {
+            result.insert(cp, category.to_string());
+        }
+    }
+
+    Ok(result)
+}
+
+/// Converts the output of [parse] into a table of [TABLE_LEN] entries indexed
+/// by codepoint, where each entry has the [IDENTIFIER_START] and
+/// [IDENTIFIER_CONTINUE] bits set according to `properties`.
+pub fn to_identifier_bits(
+    map: &BTreeMap<u32, HashSet<String>>,
+    properties: IdentifierProperties,
+) -> Box<[u8]> {
+    let read = match properties {
+        IdentifierProperties::Id => {
+            [IdentifierProperty::IdStart, IdentifierProperty::IdContinue]
+        }
+        IdentifierProperties::Xid => [
+            IdentifierProperty::XidStart,
+            IdentifierProperty::XidContinue,
+        ],
+    };
+
+    let mut table = vec![0u8; TABLE_LEN].into_boxed_slice();
+    for (&cp, props) in map.range(..TABLE_LEN as u32) {
+        table[cp as usize] = read
+            .iter()
+            .filter(|x| props.contains(x.name()))
+            .fold(0, |bits, x| bits | x.bit());
+    }
+    table
+}
+
+/// Reads in an overrides file into a [BTreeMap] from each codepoint to the
+/// identifier bits it should be forced to, where [IDENTIFIER_START] and
+/// [IDENTIFIER_CONTINUE] are the bits for `*_Start` and `*_Continue`
+/// respectively.
+///
+/// The file uses the same line format as `DerivedCoreProperties.txt`, except
+/// the right hand side is either `deny`, which clears both bits, or a comma
+/// separated list of `start` and `continue`:
+///
+/// ```text
+/// 00AA; deny
+/// E000..E0FF; start, continue
+/// ```
+pub fn parse_overrides<R: io::Read>(
+    reader: R,
+) -> Result<BTreeMap<u32, u8>, Error> {
+    let mut reader = BufReader::new(reader);
+    let mut buf = String::new();
+    let mut result = BTreeMap::new();
     loop {
         buf.clear();
         if reader.read_line(&mut buf)? == 0 {
@@ -49,32 +530,184 @@ pub fn parse<R: io::Read>(
             continue;
         }
 
-        let Some((codepoint_range, prop_name)) = trimmed.split_once(';')
-        else {
+        let Some((codepoint_range, classes)) = trimmed.split_once(';') else {
             return Err(Error::MissingDelimiter(trimmed.to_string()));
         };
 
-        let prop_name = prop_name.trim();
-        let (start_range, end_range) = parse_range(codepoint_range)?;
+        let classes = classes.trim();
+        let bits = if classes == "deny" {
+            0
+        } else {
+            let mut bits = 0;
+            for class in classes.split(',') {
+                bits |= match class.trim() {
+                    "start" => IDENTIFIER_START,
+                    "continue" => IDENTIFIER_CONTINUE,
+                    _ => {
+                        return Err(Error::UnknownOverride(
+                            classes.to_string(),
+                        ));
+                    }
+                };
+            }
+            bits
+        };
 
+        let (start_range, end_range) = parse_range(codepoint_range)?;
         for cp in start_range..=end_range {
-            if let Some(x) = result.get_mut(&cp) {
-                x.insert(prop_name.to_string());
-            } else {
-                result.insert(cp, HashSet::from_iter([prop_name.to_string()]));
-            }
+            result.insert(cp, bits);
         }
     }
 
     Ok(result)
 }
 
-fn parse_range(raw: &str) -> Result<(u32, u32), ParseIntError> {
+/// Replaces the entries of `table`, indexed by codepoint, with the bits from
+/// `overrides` as returned by [parse_overrides]. Overrides for codepoints past
+/// the end of `table` are ignored.
+pub fn apply_overrides(table: &mut [u8], overrides: &BTreeMap<u32, u8>) {
+    for (&cp, &bits) in overrides {
+        if let Some(entry) = table.get_mut(cp as usize) {
+            *entry = bits;
+        }
+    }
+}
+
+/// Clears the entries of `table`, indexed by codepoint, for every codepoint
+/// which isn't in one of the scripts named in `include`, according to
+/// `scripts` as parsed from `Scripts.txt` by [parse].
+///
+/// Script names are the long names `Scripts.txt` uses, such as `Latin`.
+/// Characters shared between scripts, such as most combining marks, are in
+/// `Common` or `Inherited`, so those need to be listed to keep them. Returns
+/// an [Error::UnknownScript] if one of `include` isn't in `scripts`.
+pub fn restrict_to_scripts(
+    table: &mut [u8],
+    scripts: &BTreeMap<u32, HashSet<String>>,
+    include: &[&str],
+) -> Result<(), Error> {
+    let known = scripts.values().flatten().collect::<HashSet<_>>();
+    if let Some(name) =
+        include.iter().find(|&&x| !known.contains(&x.to_string()))
+    {
+        return Err(Error::UnknownScript(name.to_string()));
+    }
+
+    for (cp, entry) in table.iter_mut().enumerate() {
+        let included = scripts
+            .get(&(cp as u32))
+            .is_some_and(|x| include.iter().any(|&name| x.contains(name)));
+        if !included {
+            *entry = 0;
+        }
+    }
+    Ok(())
+}
+
+/// Checks that every entry of `table`, indexed by codepoint, with the
+/// [IDENTIFIER_START] bit set also has the [IDENTIFIER_CONTINUE] bit set.
+///
+/// UAX #31 guarantees `XID_Start` is a subset of `XID_Continue` (and likewise
+/// for `ID_Start` and `ID_Continue`), so a table violating this was built from
+/// bad data or a bad override. Returns the first offending codepoint in an
+/// [Error::StartWithoutContinue].
+pub fn check_start_implies_continue(table: &[u8]) -> Result<(), Error> {
+    let bits = IDENTIFIER_START | IDENTIFIER_CONTINUE;
+    match table.iter().position(|&x| x & bits == IDENTIFIER_START) {
+        Some(cp) => Err(Error::StartWithoutContinue(cp as u32)),
+        None => Ok(()),
+    }
+}
+
+/// Checks that `table`, built by [to_identifier_bits] with `properties`, has
+/// at least one entry with the [IDENTIFIER_START] bit set.
+///
+/// A file without any identifier properties, such as `UnicodeData.txt` given
+/// in place of `DerivedCoreProperties.txt`, can parse into a table which
+/// classifies every codepoint as neither start nor continue. Build scripts
+/// should call this so that fails the build with an [Error::NoStartCodepoints]
+/// instead.
+pub fn check_has_start(
+    table: &[u8],
+    properties: IdentifierProperties,
+) -> Result<(), Error> {
+    if table.iter().any(|&x| x & IDENTIFIER_START != 0) {
+        return Ok(());
+    }
+
+    let start = match properties {
+        IdentifierProperties::Id => IdentifierProperty::IdStart,
+        IdentifierProperties::Xid => IdentifierProperty::XidStart,
+    };
+    Err(Error::NoStartCodepoints(start.name()))
+}
+
+/// Converts `index` into one of the `u16` indices used by the generated
+/// tables, returning an [Error::IndexOverflow] naming `what` is being indexed
+/// if it doesn't fit. Build scripts should use this rather than a cast or an
+/// `expect`, so a future Unicode version which needs wider indices fails the
+/// build with an explanation.
+pub fn u16_index(what: &'static str, index: usize) -> Result<u16, Error> {
+    u16::try_from(index).map_err(|_| Error::IndexOverflow { what, index })
+}
+
+/// Returns the maximal runs of codepoints with the same entry in `table`,
+/// indexed by codepoint, in ascending order along with their entry. Runs of
+/// codepoints with no properties are included, so the runs cover all of
+/// `table`.
+pub fn identifier_runs(
+    table: &[u8],
+) -> impl Iterator<Item = (Range<u32>, u8)> + '_ {
+    let mut cp = 0;
+    std::iter::from_fn(move || {
+        let &value = table.get(cp)?;
+        let len = table[cp..].iter().take_while(|&&x| x == value).count();
+        let range = cp as u32..(cp + len) as u32;
+        cp += len;
+        Some((range, value))
+    })
+}
+
+/// Returns a fingerprint of the classification in `table`, indexed by
+/// codepoint, which only changes when some codepoint's class does.
+///
+/// This hashes the runs of codepoints with a nonzero class rather than the
+/// data they were parsed from, so files which differ only in comments,
+/// whitespace or how their ranges are split give the same fingerprint, as do
+/// tables which only differ in how many trailing zero entries they have. The
+/// hash is 64 bit FNV-1a, so unlike [std::hash::DefaultHasher] it's stable
+/// across Rust versions and platforms.
+pub fn identifier_set_fingerprint(table: &[u8]) -> u64 {
+    const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    let mut hash = FNV_OFFSET;
+    let mut write = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    };
+
+    for (range, value) in identifier_runs(table) {
+        if value != 0 {
+            write(&range.start.to_le_bytes());
+            write(&(range.end - range.start).to_le_bytes());
+            write(&[value]);
+        }
+    }
+
+    hash
+}
+
+fn parse_range(raw: &str) -> Result<(u32, u32), Error> {
     if let Some((start, end)) = raw.split_once("..") {
-        Ok((
-            u32::from_str_radix(start.trim(), 16)?,
-            u32::from_str_radix(end.trim(), 16)?,
-        ))
+        let start = u32::from_str_radix(start.trim(), 16)?;
+        let end = u32::from_str_radix(end.trim(), 16)?;
+        if start > end {
+            return Err(Error::InvalidRange { start, end });
+        }
+        Ok((start, end))
     } else {
         let single = u32::from_str_radix(raw.trim(), 16)?;
         Ok((single, single))
@@ -152,10 +785,7 @@ mod tests {
     impl Read for FailingReader {
         fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
             if self.cursor >= self.fail_at {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "forced read failure",
-                ));
+                return Err(io::Error::other("forced read failure"));
             }
 
             let limit = self.fail_at.min(self.data.len());
@@ -164,10 +794,7 @@ mod tests {
 
             if to_copy == 0 {
                 self.cursor = limit;
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "forced read failure",
-                ));
+                return Err(io::Error::other("forced read failure"));
             }
 
             buf[..to_copy].copy_from_slice(
@@ -199,11 +826,429 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_default_is_cached() {
+        let parsed = parse_default();
+        assert!(std::ptr::eq(parsed, parse_default()));
+        assert!(parsed[&0x41].contains("XID_Start"));
+        assert!(!parsed[&0x30].contains("XID_Start"));
+    }
+
+    #[test]
+    fn id_and_xid_are_kept_distinct() {
+        let id = to_identifier_bits(parse_default(), IdentifierProperties::Id);
+        let xid =
+            to_identifier_bits(parse_default(), IdentifierProperties::Xid);
+        let both = IDENTIFIER_START | IDENTIFIER_CONTINUE;
+
+        // the NFKC closure drops these entirely, or demotes them to continue.
+        assert_eq!((id[0x37A], xid[0x37A]), (both, 0));
+        assert_eq!((id[0x309B], xid[0x309B]), (both, 0));
+        assert_eq!((id[0xE33], xid[0xE33]), (both, IDENTIFIER_CONTINUE));
+        assert_eq!((id[0xFF9E], xid[0xFF9E]), (both, IDENTIFIER_CONTINUE));
+        // XID is a subset of ID.
+        assert!(id.iter().zip(xid.iter()).all(|(&i, &x)| i | x == i));
+    }
+
+    #[test]
+    fn property_ids_cover_derived_core_properties() {
+        let names = parse_default().values().flatten().collect::<HashSet<_>>();
+        for name in names {
+            let id = property_id(name)
+                .unwrap_or_else(|| panic!("{name} has no property id"));
+            assert_eq!(property_name(id), Some(name.as_str()));
+        }
+
+        assert_eq!(property_id("White_Space"), None);
+        assert_eq!(property_name(PROPERTY_NAMES.len() as u16), None);
+    }
+
     #[test]
     fn parse_breaks_cleanly_on_empty_input() {
         assert!(parse("".as_bytes()).unwrap().is_empty());
     }
 
+    #[test]
+    fn parse_tolerates_irregular_whitespace() {
+        let expected = parse("0041..005A ; XID_Start\n".as_bytes()).unwrap();
+        for line in [
+            "0041 .. 005A ; XID_Start\n",
+            "0041\t..\t005A\t;\tXID_Start\n",
+            "  0041  ..005A;   XID_Start  # LATIN CAPITAL LETTER A..Z\n",
+            "\t0041..  005A\t\t; XID_Start\r\n",
+        ] {
+            assert_eq!(parse(line.as_bytes()).unwrap(), expected, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn identifier_runs_cover_the_table() {
+        let table = [0, 0, 3, 3, 3, 2, 0];
+        let runs = identifier_runs(&table).collect::<Vec<_>>();
+        assert_eq!(runs, [(0..2, 0), (2..5, 3), (5..6, 2), (6..7, 0)]);
+        assert_eq!(identifier_runs(&[]).count(), 0);
+    }
+
+    #[test]
+    fn fingerprint_ignores_cosmetic_changes() {
+        let fingerprint = |data: &str| {
+            let parsed = parse(data.as_bytes()).unwrap();
+            let table = to_identifier_bits(&parsed, IdentifierProperties::Xid);
+            identifier_set_fingerprint(&table)
+        };
+
+        let original = fingerprint(
+            "0041..005A    ; XID_Start # L& [26] LATIN CAPITAL LETTER A..Z\n\
+             0041..005A    ; XID_Continue # L& [26] LATIN CAPITAL LETTER A..Z\n\
+             0030..0039    ; XID_Continue # Nd [10] DIGIT ZERO..DIGIT NINE\n",
+        );
+        // reordered, split ranges, different comments and whitespace.
+        let cosmetic = fingerprint(
+            "# Derived Property: XID_Continue\n\
+             \n\
+             0030..0039 ; XID_Continue\n\
+             0041..004D ; XID_Continue\n\
+             004E..005A ; XID_Continue # the rest\n\
+             \n\
+             # Derived Property: XID_Start\n\
+             0041..005A\t;\tXID_Start\n",
+        );
+        assert_eq!(original, cosmetic);
+
+        let changed = fingerprint(
+            "0041..005A ; XID_Start\n\
+             0041..005A ; XID_Continue\n\
+             0030..0038 ; XID_Continue\n",
+        );
+        assert_ne!(original, changed);
+    }
+
+    #[test]
+    fn properties_of_is_sorted() {
+        let parsed = parse(
+            "0041 ; XID_Start\n\
+             0041 ; Alphabetic\n\
+             0041 ; ID_Continue\n\
+             0041 ; Uppercase\n\
+             0041 ; Cased\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(
+            properties_of(&parsed, 'A').collect::<Vec<_>>(),
+            [
+                "Alphabetic",
+                "Cased",
+                "ID_Continue",
+                "Uppercase",
+                "XID_Start"
+            ]
+        );
+        assert_eq!(properties_of(&parsed, 'B').count(), 0);
+
+        let properties: Vec<_> = properties_of(parse_default(), 'a').collect();
+        assert!(properties.is_sorted(), "{properties:?}");
+        assert!(properties.contains(&"XID_Start"));
+    }
+
+    #[test]
+    fn embedded_data_matches_unicode_id_trie_rle_copy() {
+        // `unicode-id-trie-rle` generates its tables from its own copy, since
+        // it has to be in its published package, so the tests have to check
+        // it against the same data.
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../unicode-id-trie-rle/DerivedCoreProperties.txt");
+        assert!(
+            std::fs::read(&path).unwrap() == EMBEDDED_DATA,
+            "{} differs from the DerivedCoreProperties.txt at the root of \
+             the repository",
+            path.display()
+        );
+    }
+
+    #[test]
+    fn parse_str_matches_parse() {
+        let data = std::str::from_utf8(EMBEDDED_DATA).unwrap();
+        assert_eq!(parse_str(data).unwrap(), parse(EMBEDDED_DATA).unwrap());
+
+        let data = "0041..0043 ; XID_Start # comment\r\n\
+                    \t0042\t;\tCased\r\n\
+                    # only a comment\n\
+                    \n\
+                    0041..0043 ; XID_Continue";
+        assert_eq!(parse_str(data).unwrap(), parse(data.as_bytes()).unwrap());
+
+        let err = parse_str("0041 ; XID_Start\n0042\n").unwrap_err();
+        assert!(matches!(err, Error::MissingDelimiter(x) if x == "0042"));
+    }
+
+    #[test]
+    fn parse_sorted_matches_parse() {
+        let data = "0041..0043 ; XID_Start\n\
+                    0042 ; Alphabetic\n\
+                    0042 ; Cased\n\
+                    0041..0043 ; XID_Continue\n";
+        let sorted = parse_sorted(data.as_bytes()).unwrap();
+        let parsed = parse(data.as_bytes()).unwrap();
+        assert!(sorted.keys().eq(parsed.keys()));
+        for (cp, properties) in &sorted {
+            assert!(
+                properties
+                    .iter()
+                    .eq(properties_of(&parsed, char::from_u32(*cp).unwrap()))
+            );
+        }
+        assert_eq!(
+            format!("{:?}", sorted[&0x42]),
+            r#"{"Alphabetic", "Cased", "XID_Continue", "XID_Start"}"#
+        );
+    }
+
+    #[test]
+    fn parse_many_unions_properties() {
+        let derived = "0041..0043 ; XID_Start\n0030 ; XID_Continue\n";
+        let prop_list = "0041 ; Hex_Digit\n0020 ; White_Space\n";
+        let parsed =
+            parse_many([derived.as_bytes(), prop_list.as_bytes()]).unwrap();
+
+        let props = |names: &[&str]| {
+            names.iter().map(|x| x.to_string()).collect::<HashSet<_>>()
+        };
+        let expected = BTreeMap::from([
+            (0x20, props(&["White_Space"])),
+            (0x30, props(&["XID_Continue"])),
+            (0x41, props(&["XID_Start", "Hex_Digit"])),
+            (0x42, props(&["XID_Start"])),
+            (0x43, props(&["XID_Start"])),
+        ]);
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn overrides_change_identifier_class() {
+        let derived = "00AA ; XID_Start\n00AA ; XID_Continue\n";
+        let mut table = to_identifier_bits(
+            &parse(derived.as_bytes()).unwrap(),
+            IdentifierProperties::Xid,
+        );
+        assert_eq!(table[0xAA], IDENTIFIER_START | IDENTIFIER_CONTINUE);
+
+        let overrides = "# ban the feminine ordinal indicator\n\
+                         00AA; deny\n\
+                         00C0..00C1 ; continue\n\
+                         00D7; start,continue\n";
+        let overrides = parse_overrides(overrides.as_bytes()).unwrap();
+        apply_overrides(&mut table, &overrides);
+        assert_eq!(table[0xAA], 0);
+        assert_eq!(table[0xC0], IDENTIFIER_CONTINUE);
+        assert_eq!(table[0xC1], IDENTIFIER_CONTINUE);
+        assert_eq!(table[0xD7], IDENTIFIER_START | IDENTIFIER_CONTINUE);
+    }
+
+    #[test]
+    fn parse_general_categories_reads_annotations() {
+        let derived = "# Derived Property: XID_Start\n\
+                       0041..005A ; XID_Start # L&  [26] LATIN CAPITAL..\n\
+                       2160..2162 ; XID_Start # Nl   [3] ROMAN NUMERAL..\n\
+                       0030 ; XID_Continue\n\
+                       \n";
+        let categories = parse_general_categories(derived.as_bytes()).unwrap();
+        assert_eq!(categories.len(), 26 + 3);
+        assert_eq!(categories[&0x41], "L&");
+        assert_eq!(categories[&0x5A], "L&");
+        assert_eq!(categories[&0x2161], "Nl");
+        assert!(!categories.contains_key(&0x30));
+    }
+
+    #[test]
+    fn identifier_property_from_str() {
+        use IdentifierProperty::*;
+        for (name, prop, bit) in [
+            ("ID_Start", IdStart, IDENTIFIER_START),
+            ("ID_Continue", IdContinue, IDENTIFIER_CONTINUE),
+            ("XID_Start", XidStart, IDENTIFIER_START),
+            ("XID_Continue", XidContinue, IDENTIFIER_CONTINUE),
+        ] {
+            assert_eq!(name.parse::<IdentifierProperty>().unwrap(), prop);
+            assert_eq!(prop.name(), name);
+            assert_eq!(prop.bit(), bit);
+        }
+
+        for name in ["Alphabetic", "xid_start", " XID_Start", ""] {
+            let err = name.parse::<IdentifierProperty>().unwrap_err();
+            assert!(matches!(err, Error::UnknownProperty(x) if x == name));
+        }
+    }
+
+    #[test]
+    fn to_identifier_bits_selects_properties() {
+        // U+037A is ID_Start but not XID_Start, since its NFKC form starts
+        // with a space.
+        let derived = "0041 ; ID_Start\n0041 ; ID_Continue\n\
+                       0041 ; XID_Start\n0041 ; XID_Continue\n\
+                       037A ; ID_Start\n037A ; ID_Continue\n\
+                       037A ; XID_Continue\n\
+                       110000 ; XID_Start\n";
+        let parsed = parse(derived.as_bytes()).unwrap();
+
+        let both = IDENTIFIER_START | IDENTIFIER_CONTINUE;
+        let id = to_identifier_bits(&parsed, IdentifierProperties::Id);
+        assert_eq!(id.len(), TABLE_LEN);
+        assert_eq!((id[0x41], id[0x37A], id[0x42]), (both, both, 0));
+
+        let xid = to_identifier_bits(&parsed, IdentifierProperties::Xid);
+        assert_eq!(xid.len(), TABLE_LEN);
+        assert_eq!(
+            (xid[0x41], xid[0x37A], xid[0x42]),
+            (both, IDENTIFIER_CONTINUE, 0)
+        );
+    }
+
+    #[test]
+    fn start_without_continue_is_rejected() {
+        let derived = "0041 ; XID_Start\n0041 ; XID_Continue\n\
+                       00AA ; XID_Start\n";
+        let mut table = to_identifier_bits(
+            &parse(derived.as_bytes()).unwrap(),
+            IdentifierProperties::Xid,
+        );
+
+        let err = check_start_implies_continue(&table).unwrap_err();
+        assert!(matches!(err, Error::StartWithoutContinue(0xAA)));
+
+        table[0xAA] |= IDENTIFIER_CONTINUE;
+        assert!(check_start_implies_continue(&table).is_ok());
+    }
+
+    #[test]
+    fn restricting_to_latin_clears_greek() {
+        let derived = "0041..005A ; XID_Start\n0041..005A ; XID_Continue\n\
+                       00C0 ; XID_Start\n00C0 ; XID_Continue\n\
+                       03B1 ; XID_Start\n03B1 ; XID_Continue\n\
+                       0300 ; XID_Continue\n";
+        let scripts = "0041..005A ; Latin\n00C0 ; Latin\n\
+                       03B1 ; Greek\n0300 ; Inherited\n";
+        let scripts = parse(scripts.as_bytes()).unwrap();
+        let mut table = to_identifier_bits(
+            &parse(derived.as_bytes()).unwrap(),
+            IdentifierProperties::Xid,
+        );
+
+        let both = IDENTIFIER_START | IDENTIFIER_CONTINUE;
+        restrict_to_scripts(&mut table, &scripts, &["Latin"]).unwrap();
+        assert_eq!((table[0x41], table[0xC0]), (both, both));
+        assert_eq!((table[0x3B1], table[0x300]), (0, 0));
+
+        let err = restrict_to_scripts(&mut table, &scripts, &["Klingon"])
+            .unwrap_err();
+        assert!(matches!(err, Error::UnknownScript(x) if x == "Klingon"));
+    }
+
+    #[test]
+    fn data_without_identifier_properties_is_rejected() {
+        let derived = "# not DerivedCoreProperties.txt\n\
+                       0041..005A ; Uppercase\n\
+                       0061..007A ; Lowercase\n";
+        let table = to_identifier_bits(
+            &parse(derived.as_bytes()).unwrap(),
+            IdentifierProperties::Xid,
+        );
+        let err =
+            check_has_start(&table, IdentifierProperties::Xid).unwrap_err();
+        assert!(matches!(err, Error::NoStartCodepoints("XID_Start")));
+        assert_eq!(
+            err.to_string(),
+            "no XID_Start codepoints found — wrong data file?"
+        );
+
+        let table = to_identifier_bits(
+            &parse("0041 ; ID_Start\n0041 ; ID_Continue\n".as_bytes())
+                .unwrap(),
+            IdentifierProperties::Id,
+        );
+        assert!(check_has_start(&table, IdentifierProperties::Id).is_ok());
+    }
+
+    #[test]
+    fn u16_index_overflow_is_a_clear_error() {
+        // alternating classes give every codepoint a run of its own, which is
+        // far more runs than a u16 can index.
+        let table = (0..0x20000)
+            .map(|cp| if cp % 2 == 0 { 0 } else { IDENTIFIER_CONTINUE })
+            .collect::<Vec<_>>();
+        let runs = 1 + table.windows(2).filter(|x| x[0] != x[1]).count();
+
+        let err = u16_index("runs", runs).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::IndexOverflow {
+                what: "runs",
+                index: 0x20000
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "runs don't fit in a u16 index (131072 > 65535), the generated \
+             tables need to be widened to u32"
+        );
+        assert_eq!(u16_index("runs", 0xffff).unwrap(), 0xffff);
+    }
+
+    #[cfg(feature = "ucd-xml")]
+    #[test]
+    fn parse_xml_matches_parse() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ucd xmlns="http://www.unicode.org/ns/2003/ucd/1.0">
+  <repertoire>
+    <char cp="0030" na="DIGIT ZERO" IDS="N" IDC="Y" XIDS="N" XIDC="Y"/>
+    <char cp="0041" na="LATIN CAPITAL LETTER A" IDS="Y" IDC="Y" XIDS="Y"
+          XIDC="Y"/>
+    <char cp="037A" na="GREEK YPOGEGRAMMENI" IDS="Y" IDC="Y" XIDS="N"
+          XIDC="Y"><name-alias alias="IOTA SUBSCRIPT" type="figment"/></char>
+    <char first-cp="3400" last-cp="3402" na="CJK UNIFIED IDEOGRAPH-#"
+          IDS="Y" IDC="Y" XIDS="Y" XIDC="Y"/>
+    <reserved first-cp="0378" last-cp="0379" IDS="N" IDC="N" XIDS="N"
+              XIDC="N"/>
+  </repertoire>
+</ucd>"#;
+        let txt = "0030 ; ID_Continue\n0030 ; XID_Continue\n\
+                   0041 ; ID_Start\n0041 ; ID_Continue\n\
+                   0041 ; XID_Start\n0041 ; XID_Continue\n\
+                   037A ; ID_Start\n037A ; ID_Continue\n\
+                   037A ; XID_Continue\n\
+                   3400..3402 ; ID_Start\n3400..3402 ; ID_Continue\n\
+                   3400..3402 ; XID_Start\n3400..3402 ; XID_Continue\n";
+
+        assert_eq!(
+            parse_xml(xml.as_bytes()).unwrap(),
+            parse(txt.as_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_overrides_rejects_unknown_classes() {
+        let err = parse_overrides("00AA; allow\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::UnknownOverride(_)));
+    }
+
+    #[test]
+    fn parse_rejects_descending_ranges() {
+        let err = parse("005A..0041 ; XID_Start\n".as_bytes()).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidRange {
+                start: 0x5a,
+                end: 0x41
+            }
+        ));
+        assert!(parse_overrides("005A..0041 ; deny\n".as_bytes()).is_err());
+        // a range of one codepoint is still fine.
+        assert_eq!(
+            parse("0041..0041 ; XID_Start\n".as_bytes()).unwrap().len(),
+            1
+        );
+    }
+
     #[test]
     fn parse_errors_when_missing_semicolon() {
         let contents =
@@ -6,6 +6,10 @@ use std::{
     path::PathBuf,
 };
 
+use unicode_id_trie_rle_derived_core_properties::{
+    IdentifierProperties, to_identifier_bits,
+};
+
 const MAX_CODEPOINT: u32 = 0x10ffff;
 const START_CODEPOINT: u32 = 0x80;
 const BYTES_PER_LINE: usize = 12;
@@ -21,23 +25,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     let file = File::open(&derived)?;
     let parsed = unicode_id_trie_rle_derived_core_properties::parse(file)?;
 
-    let mut table = vec![0u8; (MAX_CODEPOINT + 1) as usize];
-    for (ch, props) in parsed {
-        if (ch as u32) > MAX_CODEPOINT {
-            continue;
-        }
-
-        let mut bits = 0u8;
-        for prop in props {
-            if prop.contains("XID_Start") {
-                bits |= 1;
-            }
-            if prop.contains("XID_Continue") {
-                bits |= 2;
-            }
-        }
-        table[ch as usize] = bits;
-    }
+    let table = to_identifier_bits(&parsed, IdentifierProperties::Xid);
 
     let end_cp = MAX_CODEPOINT + 1; // sentinel run start
     let mut runs = Vec::with_capacity(1024);
@@ -57,7 +45,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     }
     runs.push((run_start, current));
-    if runs.last().map_or(true, |(s, _)| *s != end_cp) {
+    if runs.last().is_none_or(|(s, _)| *s != end_cp) {
         runs.push((end_cp, 0));
     }
 
@@ -84,7 +72,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         u8::try_from(bits.max(1)).expect("bits per index fits in u8")
     };
     let mut index_bytes = Vec::with_capacity(
-        ((block_index.len() * bits_per_index as usize) + 7) / 8,
+        (block_index.len() * bits_per_index as usize).div_ceil(8),
     );
     let mut acc: u32 = 0;
     let mut acc_bits: u8 = 0;
@@ -122,10 +110,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         writer,
         "pub(crate) const INDEX_BITS: u32 = {bits_per_index};"
     )?;
+    let runs_len = runs.len() * 3;
     writeln!(
         writer,
-        "pub(crate) static RUNS: [u8; {}] = [",
-        runs.len() * 3
+        "static RUNS_ALIGNED: Aligned16<[u8; {runs_len}]> = Aligned16(["
     )?;
     for (idx, (start, value)) in runs.iter().enumerate() {
         let offset = idx * 3;
@@ -146,7 +134,11 @@ fn main() -> Result<(), Box<dyn Error>> {
     if (runs.len() * 3) % BYTES_PER_LINE != 0 {
         writeln!(writer)?;
     }
-    writeln!(writer, "];")?;
+    writeln!(writer, "]);")?;
+    writeln!(
+        writer,
+        "pub(crate) static RUNS: &[u8; {runs_len}] = &RUNS_ALIGNED.0;"
+    )?;
 
     writeln!(
         writer,
@@ -167,6 +159,26 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     }
     writeln!(writer, "];")?;
+
+    // The runs start at START_CODEPOINT, so they don't cover ASCII. The tests
+    // check the hand-written ASCII_TABLE against these instead.
+    writeln!(writer, "#[cfg(test)]")?;
+    writeln!(
+        writer,
+        "pub(crate) static DATA_ASCII_CLASSES: [u8; {START_CODEPOINT}] = ["
+    )?;
+    for (idx, value) in table[..START_CODEPOINT as usize].iter().enumerate() {
+        if idx % INDEX_BYTES_PER_LINE == 0 {
+            write!(writer, "\t")?;
+        }
+        write!(writer, "0x{value:02x},")?;
+        if idx % INDEX_BYTES_PER_LINE == INDEX_BYTES_PER_LINE - 1 {
+            writeln!(writer)?;
+        } else {
+            write!(writer, " ")?;
+        }
+    }
+    writeln!(writer, "];")?;
     writer.flush()?;
 
     Ok(())
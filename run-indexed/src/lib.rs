@@ -4,9 +4,48 @@ const IDENTIFIER_CONTINUE: u8 = 2;
 const START_CODEPOINT: u32 = 0x80;
 
 include!(concat!(env!("OUT_DIR"), "/table.rs"));
+
+/// Aligns the run table, so vectorized lookups can use aligned loads.
+#[repr(C, align(16))]
+struct Aligned16<T>(T);
+
 const INDEX_MASK: u32 = (1u32 << INDEX_BITS) - 1;
 const ASCII_TABLE: [u8; 128] = ascii_table();
 
+/// The number of bytes of static storage used by the lookup tables, including
+/// the ASCII fast path table.
+pub const TABLE_BYTES: usize =
+    size_of_val(RUNS) + size_of_val(&BLOCK_INDEX) + size_of_val(&ASCII_TABLE);
+
+/// The number of runs in the run table, not counting the sentinel at the end.
+pub const RUN_COUNT: usize = RUNS.len() / RUN_BYTES - 1;
+
+/// The number of bytes each run takes up in the run table.
+const RUN_BYTES: usize = 3;
+
+/// The structural counts of the lookup tables, as returned by
+/// [structure_summary].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Summary {
+    /// See [RUN_COUNT].
+    pub runs: usize,
+    /// The number of blocks with an entry in the block index.
+    pub blocks: usize,
+    /// See [TABLE_BYTES].
+    pub table_bytes: usize,
+}
+
+/// Returns the structural counts of the lookup tables, which is useful for
+/// comparing encodings alongside [TABLE_BYTES].
+pub const fn structure_summary() -> Summary {
+    Summary {
+        runs: RUN_COUNT,
+        blocks: BLOCK_COUNT,
+        table_bytes: TABLE_BYTES,
+    }
+}
+
 pub struct UnicodeIdentifierClass(u8);
 
 impl UnicodeIdentifierClass {
@@ -21,16 +60,27 @@ impl UnicodeIdentifierClass {
     }
 }
 
+/// Decodes the run at `idx`. `CHECKED` selects bounds checked indexing, which
+/// only exists to measure what skipping the checks buys (see the `checked`
+/// feature).
 #[inline]
-fn load_run(runs: &[u8], idx: usize) -> (u32, u8) {
-    // `build.rs` appends a sentinel run so idx + 1 is always in-bounds.
-    let base = idx * 3;
-    let (b0, b1, b2) = unsafe {
+fn load_run<const CHECKED: bool>(runs: &[u8], idx: usize) -> (u32, u8) {
+    let base = idx * RUN_BYTES;
+    let (b0, b1, b2) = if CHECKED {
         (
-            *runs.get_unchecked(base) as u32,
-            *runs.get_unchecked(base + 1) as u32,
-            *runs.get_unchecked(base + 2) as u32,
+            runs[base] as u32,
+            runs[base + 1] as u32,
+            runs[base + 2] as u32,
         )
+    } else {
+        // `build.rs` appends a sentinel run so idx + 1 is always in-bounds.
+        unsafe {
+            (
+                *runs.get_unchecked(base) as u32,
+                *runs.get_unchecked(base + 1) as u32,
+                *runs.get_unchecked(base + 2) as u32,
+            )
+        }
     };
     let start = b0 | (b1 << 8) | ((b2 & 0x1f) << 16);
     let value = (b2 >> 5) as u8 & 3;
@@ -42,7 +92,7 @@ fn block_index(block: usize) -> usize {
     debug_assert!(block < BLOCK_COUNT);
     let bit_offset = (block as u32) * INDEX_BITS;
     let byte = (bit_offset >> 3) as usize;
-    let shift = (bit_offset & 7) as u32;
+    let shift = bit_offset & 7;
     let chunk = u32::from_le_bytes([
         BLOCK_INDEX[byte],
         BLOCK_INDEX[byte + 1],
@@ -56,6 +106,19 @@ fn block_index(block: usize) -> usize {
 /// `XID_Start` or the properties `ID_Continue` or `XID_Continue`.
 #[inline]
 pub fn unicode_identifier_class(cp: char) -> UnicodeIdentifierClass {
+    classify::<false>(cp)
+}
+
+/// Like [unicode_identifier_class], but bounds checks every access to the run
+/// table instead of relying on `unsafe`.
+#[cfg(feature = "checked")]
+#[inline]
+pub fn unicode_identifier_class_checked(cp: char) -> UnicodeIdentifierClass {
+    classify::<true>(cp)
+}
+
+#[inline]
+fn classify<const CHECKED: bool>(cp: char) -> UnicodeIdentifierClass {
     // ASCII fast path via table to avoid unpredictable branches.
     if (cp as u32) < START_CODEPOINT {
         return UnicodeIdentifierClass(ASCII_TABLE[cp as usize]);
@@ -71,8 +134,8 @@ pub fn unicode_identifier_class(cp: char) -> UnicodeIdentifierClass {
     let block = (cp >> SHIFT) as usize;
     let runs = &RUNS[..];
     let mut idx = block_index(block);
-    let (mut run_start, mut run_value) = load_run(runs, idx);
-    let (mut next_start, mut next_value) = load_run(runs, idx + 1);
+    let (mut run_start, mut run_value) = load_run::<CHECKED>(runs, idx);
+    let (mut next_start, mut next_value) = load_run::<CHECKED>(runs, idx + 1);
     loop {
         if cp < next_start {
             if cp >= run_start {
@@ -83,7 +146,7 @@ pub fn unicode_identifier_class(cp: char) -> UnicodeIdentifierClass {
         idx += 1;
         run_start = next_start;
         run_value = next_value;
-        let (ns, nv) = load_run(runs, idx + 1);
+        let (ns, nv) = load_run::<CHECKED>(runs, idx + 1);
         next_start = ns;
         next_value = nv;
     }
@@ -114,7 +177,7 @@ const fn ascii_table() -> [u8; 128] {
 /// Unicode Standard Annex #31.
 #[inline]
 pub fn is_identifier(cp: &[char]) -> bool {
-    if cp.len() == 0 {
+    if cp.is_empty() {
         return false;
     }
 
@@ -122,7 +185,7 @@ pub fn is_identifier(cp: &[char]) -> bool {
         return false;
     }
 
-    for (i, c) in cp.into_iter().enumerate() {
+    for (i, c) in cp.iter().enumerate() {
         if !unicode_identifier_class(*c).is_continue() {
             // the two special characters are only allowed in the
             // middle, not the end.
@@ -135,25 +198,44 @@ pub fn is_identifier(cp: &[char]) -> bool {
     true
 }
 
+/// Returns the [UnicodeIdentifierClass] of the first `char` in `s`, or `None`
+/// if `s` is empty.
+#[inline]
+pub fn first_char_class(s: &str) -> Option<UnicodeIdentifierClass> {
+    first_char_class_impl::<false>(s)
+}
+
+#[inline]
+fn first_char_class_impl<const CHECKED: bool>(
+    s: &str,
+) -> Option<UnicodeIdentifierClass> {
+    s.chars().next().map(classify::<CHECKED>)
+}
+
 /// Checks if a given string is a unicode identifier, defined by Unicode
 /// Standard Annex #31.
 #[inline]
 pub fn str_is_identifier(s: &str) -> bool {
-    if s.len() == 0 {
-        return false;
-    }
+    str_is_identifier_impl::<false>(s)
+}
+
+/// Like [str_is_identifier], but bounds checks every access to the run table
+/// instead of relying on `unsafe`.
+#[cfg(feature = "checked")]
+#[inline]
+pub fn str_is_identifier_checked(s: &str) -> bool {
+    str_is_identifier_impl::<true>(s)
+}
 
-    if !unicode_identifier_class(
-        s.chars().nth(0).expect("we already checked that len > 0"),
-    )
-    .is_start()
-    {
+#[inline]
+fn str_is_identifier_impl<const CHECKED: bool>(s: &str) -> bool {
+    if !first_char_class_impl::<CHECKED>(s).is_some_and(|x| x.is_start()) {
         return false;
     }
 
     let cp = s.chars().collect::<Vec<_>>();
     for (i, c) in cp.iter().enumerate() {
-        if !unicode_identifier_class(*c).is_continue() {
+        if !classify::<CHECKED>(*c).is_continue() {
             // the two special characters are only allowed in the
             // middle, not the end.
             if (*c != '\u{200c}' && *c != '\u{200d}') || i + 1 == cp.len() {
@@ -169,44 +251,42 @@ pub fn str_is_identifier(s: &str) -> bool {
 mod tests {
     use super::*;
     use proptest::prelude::*;
-    use std::{fs::File, path::PathBuf, sync::OnceLock};
-
-    const MAX_SCALAR: usize = 0x110000;
+    use std::sync::OnceLock;
+    use unicode_id_trie_rle_derived_core_properties::{
+        IdentifierProperties, parse_default, to_identifier_bits,
+    };
 
     fn derived_identifier_table() -> &'static [u8] {
         static TABLE: OnceLock<Box<[u8]>> = OnceLock::new();
-        TABLE
-            .get_or_init(|| {
-                let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-                let derived_path =
-                    manifest_dir.join("../DerivedCoreProperties.txt");
-                let file = File::open(&derived_path).unwrap_or_else(|err| {
-                    panic!("failed to open {}: {err}", derived_path.display())
-                });
-
-                let parsed =
-                    unicode_id_trie_rle_derived_core_properties::parse(file)
-                        .unwrap_or_else(|err| {
-                            panic!("failed to parse derived data: {err}")
-                        });
-                let mut table = vec![0u8; MAX_SCALAR];
-                for (ch, props) in parsed {
-                    let mut bits = 0u8;
-                    for prop in props {
-                        if prop.contains("XID_Start") {
-                            bits |= IDENTIFIER_START;
-                        }
-                        if prop.contains("XID_Continue") {
-                            bits |= IDENTIFIER_CONTINUE;
-                        }
-                    }
-
-                    table[ch as usize] = bits;
-                }
-
-                table.into_boxed_slice()
-            })
-            .as_ref()
+        TABLE.get_or_init(|| {
+            to_identifier_bits(parse_default(), IdentifierProperties::Xid)
+        })
+    }
+
+    #[test]
+    fn structure_counts_are_consistent() {
+        let summary = structure_summary();
+        assert!(summary.runs > 0);
+        assert!(summary.blocks > 0);
+        assert_eq!(RUNS.len(), (RUN_COUNT + 1) * RUN_BYTES);
+        let index_bits = BLOCK_COUNT * INDEX_BITS as usize;
+        assert!(BLOCK_INDEX.len() >= index_bits.div_ceil(8));
+        assert!((0..BLOCK_COUNT).all(|block| block_index(block) < RUN_COUNT));
+    }
+
+    #[test]
+    fn ascii_table_matches_data() {
+        for cp in 0..START_CODEPOINT {
+            assert_eq!(
+                ASCII_TABLE[cp as usize], DATA_ASCII_CLASSES[cp as usize],
+                "U+{cp:04X}"
+            );
+        }
+    }
+
+    #[test]
+    fn run_table_is_aligned() {
+        assert_eq!(RUNS.as_ptr() as usize % 16, 0);
     }
 
     #[test]
@@ -266,6 +346,16 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "checked")]
+    proptest! {
+        #[test]
+        fn checked_and_unchecked_agree(cp in any::<char>()) {
+            let unchecked = unicode_identifier_class(cp);
+            let checked = unicode_identifier_class_checked(cp);
+            prop_assert_eq!(unchecked.0, checked.0, "U+{:04X}", cp as u32);
+        }
+    }
+
     proptest! {
         #[test]
         fn str_and_slice_identifier_agree(chars in prop::collection::vec(any::<char>(), 0..16)) {
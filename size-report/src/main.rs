@@ -0,0 +1,32 @@
+//! Prints the static table size of each encoding side by side, along with
+//! their size relative to `baseline` and `unicode-id-trie-rle`.
+//!
+//! CLI usage:
+//! - `cargo run -p size-report`
+
+const ENCODINGS: [(&str, usize); 5] = [
+    ("baseline", baseline::TABLE_BYTES),
+    ("run-indexed", run_indexed::TABLE_BYTES),
+    ("delta-encoded", delta_encoded::TABLE_BYTES),
+    ("unicode-id-trie-rle", unicode_id_trie_rle::TABLE_BYTES),
+    ("adaptive", adaptive::TABLE_BYTES),
+];
+
+fn main() {
+    let baseline = ENCODINGS[0].1 as f64;
+    let trie = ENCODINGS[3].1 as f64;
+
+    println!(
+        "{:<20} {:>10} {:>12} {:>12}",
+        "encoding", "bytes", "vs baseline", "vs trie-rle"
+    );
+    for (name, bytes) in ENCODINGS {
+        println!(
+            "{:<20} {:>10} {:>11.2}x {:>11.2}x",
+            name,
+            bytes,
+            bytes as f64 / baseline,
+            bytes as f64 / trie,
+        );
+    }
+}
@@ -94,44 +94,16 @@ pub fn str_is_identifier(s: &str) -> bool {
 mod tests {
     use super::*;
     use proptest::prelude::*;
-    use std::{fs::File, path::PathBuf, sync::OnceLock};
-
-    const MAX_SCALAR: usize = 0x110000;
+    use std::sync::OnceLock;
+    use unicode_id_trie_rle_derived_core_properties::{
+        IdentifierProperties, parse_default, to_identifier_bits,
+    };
 
     fn derived_identifier_table() -> &'static [u8] {
         static TABLE: OnceLock<Box<[u8]>> = OnceLock::new();
-        TABLE
-            .get_or_init(|| {
-                let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-                let derived_path =
-                    manifest_dir.join("../DerivedCoreProperties.txt");
-                let file = File::open(&derived_path).unwrap_or_else(|err| {
-                    panic!("failed to open {}: {err}", derived_path.display())
-                });
-
-                let parsed =
-                    unicode_id_trie_rle_derived_core_properties::parse(file)
-                        .unwrap_or_else(|err| {
-                            panic!("failed to parse derived data: {err}")
-                        });
-                let mut table = vec![0u8; MAX_SCALAR];
-                for (ch, props) in parsed {
-                    let mut bits = 0u8;
-                    for prop in props {
-                        if prop.contains("ID_Start") {
-                            bits |= IDENTIFIER_START;
-                        }
-                        if prop.contains("ID_Continue") {
-                            bits |= IDENTIFIER_CONTINUE;
-                        }
-                    }
-
-                    table[ch as usize] = bits;
-                }
-
-                table.into_boxed_slice()
-            })
-            .as_ref()
+        TABLE.get_or_init(|| {
+            to_identifier_bits(parse_default(), IdentifierProperties::Id)
+        })
     }
 
     #[test]
@@ -1,47 +1,169 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     env,
     error::Error,
-    fs::File,
-    io::{BufWriter, Write},
-    path::PathBuf,
+    fs::{self, File},
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use unicode_id_trie_rle_derived_core_properties::{
+    IdentifierProperties, check_has_start, identifier_set_fingerprint,
+    to_identifier_bits, u16_index,
 };
 
 const MAX_CODEPOINT: u32 = 0x0fffff; // decoder ignores codepoints beyond this
 const START_CODEPOINT: u32 = 0x80;
 const SHIFT: u32 = 10;
 const TOP_BITS: u32 = 6;
+// the shapes tried by the `auto_shift` feature. The decoder relies on the
+// surrogates starting on a block boundary, which caps SHIFT at 11, and
+// `level1_rle` needs the level 1 index to fit in a u8.
+const AUTO_SHIFTS: std::ops::RangeInclusive<u32> = 6..=11;
+const AUTO_TOP_BITS: std::ops::RangeInclusive<u32> = 2..=8;
 const BYTES_PER_LINE: usize = 12;
 const INDEX_BYTES_PER_LINE: usize = 16;
 
-fn build_table() -> Result<Vec<u8>, Box<dyn Error>> {
+/// A `DerivedCoreProperties.txt` file, read once and shared by everything
+/// generated from it.
+struct DerivedData {
+    /// The contents of the file, for the general category annotations in its
+    /// comments, which [DerivedData::properties] doesn't keep.
+    text: String,
+    /// The file parsed by `parse_str`.
+    properties: BTreeMap<u32, HashSet<String>>,
+}
+
+fn build_table() -> Result<(Vec<u8>, DerivedData), Box<dyn Error>> {
     let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?);
     let derived = manifest_dir.join("./DerivedCoreProperties.txt");
     println!("cargo:rerun-if-changed={}", derived.display());
     println!("cargo:rerun-if-changed=build.rs");
 
-    let file = File::open(&derived)?;
-    let parsed = unicode_id_trie_rle_derived_core_properties::parse(file)?;
+    let (mut table, data) = parse_table(&derived)?;
+    restrict_scripts(&mut table)?;
+    apply_overrides(&mut table)?;
+    Ok((table, data))
+}
 
-    let mut table = vec![0u8; (MAX_CODEPOINT + 1) as usize];
-    for (ch, props) in parsed {
-        if (ch as u32) > MAX_CODEPOINT {
-            continue;
-        }
+/// Reads the identifier classes of every codepoint the decoder covers from
+/// the `DerivedCoreProperties.txt` at `path`, along with the file itself.
+fn parse_table(path: &Path) -> Result<(Vec<u8>, DerivedData), Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    let properties =
+        unicode_id_trie_rle_derived_core_properties::parse_str(&text)?;
 
-        let mut bits = 0u8;
-        for prop in props {
-            if prop.contains("XID_Start") {
-                bits |= 1;
-            }
-            if prop.contains("XID_Continue") {
-                bits |= 2;
-            }
-        }
-        table[ch as usize] = bits;
+    let mut table =
+        to_identifier_bits(&properties, IdentifierProperties::Xid).into_vec();
+    // a file with none of the identifier properties would otherwise build a
+    // table which classifies everything as neither.
+    check_has_start(&table, IdentifierProperties::Xid)
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    table.truncate((MAX_CODEPOINT + 1) as usize);
+    Ok((table, DerivedData { text, properties }))
+}
+
+/// Returns the Unicode version named by the header of the
+/// `DerivedCoreProperties.txt` at `path`, such as `(17, 0, 0)` for a file
+/// starting with `# DerivedCoreProperties-17.0.0.txt`.
+fn data_version(path: &Path) -> Result<(u8, u8, u8), Box<dyn Error>> {
+    let mut line = String::new();
+    BufReader::new(File::open(path)?).read_line(&mut line)?;
+    let parts: Option<Vec<u8>> = line
+        .trim()
+        .strip_prefix("# DerivedCoreProperties-")
+        .and_then(|x| x.strip_suffix(".txt"))
+        .and_then(|x| x.split('.').map(|x| x.parse().ok()).collect());
+    match parts.as_deref() {
+        Some(&[major, minor, update]) => Ok((major, minor, update)),
+        _ => Err(format!(
+            "{} doesn't start with a DerivedCoreProperties-X.Y.Z.txt header",
+            path.display()
+        )
+        .into()),
     }
+}
+
+/// Returns the script names listed in `UNICODE_INCLUDE_SCRIPTS`, if set.
+fn included_scripts() -> Option<Vec<String>> {
+    println!("cargo:rerun-if-env-changed=UNICODE_INCLUDE_SCRIPTS");
+    let scripts = env::var("UNICODE_INCLUDE_SCRIPTS").ok()?;
+    Some(
+        scripts
+            .split(',')
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+            .collect(),
+    )
+}
 
-    Ok(table)
+/// Clears the classes of every codepoint outside the scripts listed in
+/// `UNICODE_INCLUDE_SCRIPTS`, if set, according to the `Scripts.txt` named by
+/// `UNICODE_SCRIPTS_PATH`. This isn't UAX #31 compliant, it's only for
+/// shrinking the tables.
+fn restrict_scripts(table: &mut [u8]) -> Result<(), Box<dyn Error>> {
+    let Some(include) = included_scripts() else {
+        return Ok(());
+    };
+    if include.is_empty() {
+        return Err("UNICODE_INCLUDE_SCRIPTS doesn't name any scripts".into());
+    }
+    println!("cargo:rerun-if-env-changed=UNICODE_SCRIPTS_PATH");
+    let Some(path) = env::var_os("UNICODE_SCRIPTS_PATH") else {
+        return Err(
+            "UNICODE_INCLUDE_SCRIPTS needs UNICODE_SCRIPTS_PATH to be \
+                    set to the Scripts.txt for the same Unicode version"
+                .into(),
+        );
+    };
+    let path = PathBuf::from(path);
+    println!("cargo:rerun-if-changed={}", path.display());
+
+    // Scripts.txt has the same format as DerivedCoreProperties.txt, with the
+    // script name in place of the property.
+    let scripts = unicode_id_trie_rle_derived_core_properties::parse(
+        File::open(&path)?,
+    )?;
+    let include = include.iter().map(String::as_str).collect::<Vec<_>>();
+    unicode_id_trie_rle_derived_core_properties::restrict_to_scripts(
+        table, &scripts, &include,
+    )
+    .map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(())
+}
+
+/// Forces the classes of specific codepoints according to the overrides file
+/// named by `UNICODE_OVERRIDES_PATH`, if set. See
+/// [unicode_id_trie_rle_derived_core_properties::parse_overrides] for the
+/// format.
+///
+/// This is only read from the environment rather than defaulting to a file in
+/// the crate, since cargo would otherwise rerun this script on every build
+/// to check whether the file had been created.
+fn apply_overrides(table: &mut [u8]) -> Result<(), Box<dyn Error>> {
+    println!("cargo:rerun-if-env-changed=UNICODE_OVERRIDES_PATH");
+    let Some(path) = env::var_os("UNICODE_OVERRIDES_PATH") else {
+        return Ok(());
+    };
+    let path = PathBuf::from(path);
+    println!("cargo:rerun-if-changed={}", path.display());
+
+    let file = File::open(&path)?;
+    let overrides =
+        unicode_id_trie_rle_derived_core_properties::parse_overrides(file)?;
+    if let Some(&cp) = overrides.keys().find(|&&cp| cp < START_CODEPOINT) {
+        return Err(format!(
+            "{}: U+{cp:04X} can't be overridden, ASCII classes are fixed",
+            path.display()
+        )
+        .into());
+    }
+
+    unicode_id_trie_rle_derived_core_properties::apply_overrides(
+        table, &overrides,
+    );
+    Ok(())
 }
 
 fn build_runs(table: &[u8]) -> Vec<(u32, u8)> {
@@ -64,18 +186,22 @@ fn build_runs(table: &[u8]) -> Vec<(u32, u8)> {
         }
     }
     runs.push((run_start, current));
-    if runs.last().map_or(true, |(s, _)| *s != end_cp) {
+    if runs.last().is_none_or(|(s, _)| *s != end_cp) {
         runs.push((end_cp, 0));
     }
 
     runs
 }
 
-fn build_block_index(runs: &[(u32, u8)], block_count: u32) -> Vec<usize> {
+fn build_block_index(
+    runs: &[(u32, u8)],
+    shift: u32,
+    block_count: u32,
+) -> Vec<usize> {
     let mut block_index = vec![0usize; block_count as usize];
     let mut run_idx = 0usize;
     for block in 0..block_count {
-        let block_start = block << SHIFT;
+        let block_start = block << shift;
         while run_idx + 1 < runs.len() && runs[run_idx + 1].0 <= block_start {
             run_idx += 1;
         }
@@ -92,6 +218,38 @@ fn emit_u8_array(
     per_line: usize,
 ) -> Result<(), Box<dyn Error>> {
     writeln!(writer, "pub(crate) static {name}: [u8; {}] = [", data.len())?;
+    write_u8_items(writer, data, per_line)?;
+    writeln!(writer, "];")?;
+    Ok(())
+}
+
+/// Like [emit_u8_array], but aligned the same way as
+/// [emit_aligned_u16_array].
+fn emit_aligned_u8_array(
+    writer: &mut BufWriter<File>,
+    name: &str,
+    data: &[u8],
+    per_line: usize,
+) -> Result<(), Box<dyn Error>> {
+    let len = data.len();
+    writeln!(
+        writer,
+        "static {name}_ALIGNED: Aligned16<[u8; {len}]> = Aligned16(["
+    )?;
+    write_u8_items(writer, data, per_line)?;
+    writeln!(writer, "]);")?;
+    writeln!(
+        writer,
+        "pub(crate) static {name}: &[u8; {len}] = &{name}_ALIGNED.0;"
+    )?;
+    Ok(())
+}
+
+fn write_u8_items(
+    writer: &mut BufWriter<File>,
+    data: &[u8],
+    per_line: usize,
+) -> Result<(), Box<dyn Error>> {
     for (idx, byte) in data.iter().enumerate() {
         if idx % per_line == 0 {
             write!(writer, "\t")?;
@@ -103,6 +261,31 @@ fn emit_u8_array(
             write!(writer, " ")?;
         }
     }
+    Ok(())
+}
+
+fn emit_u32_array(
+    writer: &mut BufWriter<File>,
+    name: &str,
+    data: &[u32],
+    per_line: usize,
+) -> Result<(), Box<dyn Error>> {
+    writeln!(
+        writer,
+        "pub(crate) static {name}: [u32; {}] = [",
+        data.len()
+    )?;
+    for (idx, val) in data.iter().enumerate() {
+        if idx % per_line == 0 {
+            write!(writer, "\t")?;
+        }
+        write!(writer, "0x{val:08x},")?;
+        if idx % per_line == per_line - 1 || idx + 1 == data.len() {
+            writeln!(writer)?;
+        } else {
+            write!(writer, " ")?;
+        }
+    }
     writeln!(writer, "];")?;
     Ok(())
 }
@@ -118,6 +301,39 @@ fn emit_u16_array(
         "pub(crate) static {name}: [u16; {}] = [",
         data.len()
     )?;
+    write_u16_items(writer, data, per_line)?;
+    writeln!(writer, "];")?;
+    Ok(())
+}
+
+/// Like [emit_u16_array], but stores the array in an `Aligned16` so it starts
+/// on a 16 byte boundary, and names it through a reference so it can be used
+/// like any other table.
+fn emit_aligned_u16_array(
+    writer: &mut BufWriter<File>,
+    name: &str,
+    data: &[u16],
+    per_line: usize,
+) -> Result<(), Box<dyn Error>> {
+    let len = data.len();
+    writeln!(
+        writer,
+        "static {name}_ALIGNED: Aligned16<[u16; {len}]> = Aligned16(["
+    )?;
+    write_u16_items(writer, data, per_line)?;
+    writeln!(writer, "]);")?;
+    writeln!(
+        writer,
+        "pub(crate) static {name}: &[u16; {len}] = &{name}_ALIGNED.0;"
+    )?;
+    Ok(())
+}
+
+fn write_u16_items(
+    writer: &mut BufWriter<File>,
+    data: &[u16],
+    per_line: usize,
+) -> Result<(), Box<dyn Error>> {
     for (idx, val) in data.iter().enumerate() {
         if idx % per_line == 0 {
             write!(writer, "\t")?;
@@ -129,38 +345,625 @@ fn emit_u16_array(
             write!(writer, " ")?;
         }
     }
-    writeln!(writer, "];")?;
     Ok(())
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let table = build_table()?;
-    let runs = build_runs(&table);
+/// A hash and displace perfect hash over the codepoints of single codepoint
+/// runs, used by the experimental `phf` feature. A codepoint's bucket picks a
+/// displacement, which is mixed into the codepoint before hashing it into its
+/// slot.
+struct SingletonHash {
+    bucket_bits: u32,
+    slot_bits: u32,
+    displacements: Vec<u16>,
+    keys: Vec<u32>,
+    values: Vec<u8>,
+}
+
+/// The first and last codepoints of a Unicode block, along with its name.
+type Block = (u32, u32, String);
+
+/// Returns the path named by the environment variable `var`, or the copy of
+/// `file` bundled with this crate if it isn't set.
+fn data_path(var: &str, file: &str) -> Result<PathBuf, Box<dyn Error>> {
+    println!("cargo:rerun-if-env-changed={var}");
+    if let Some(path) = env::var_os(var) {
+        return Ok(PathBuf::from(path));
+    }
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?);
+    Ok(manifest_dir.join(file))
+}
+
+/// Returns the blocks from the `Blocks.txt` file named by
+/// `UNICODE_BLOCKS_PATH`, or the bundled one, which contain at least one start
+/// or continue codepoint in `table`, in ascending order.
+fn build_identifier_blocks(
+    table: &[u8],
+) -> Result<Vec<Block>, Box<dyn Error>> {
+    let path = data_path("UNICODE_BLOCKS_PATH", "Blocks.txt")?;
+    println!("cargo:rerun-if-changed={}", path.display());
+
+    // Blocks.txt has the same format as DerivedCoreProperties.txt, with the
+    // block name in place of the property.
+    let parsed = unicode_id_trie_rle_derived_core_properties::parse(
+        File::open(&path)?,
+    )?;
+    let mut blocks: Vec<Block> = Vec::new();
+    for (cp, names) in parsed {
+        let Some(name) = names.into_iter().next() else {
+            continue;
+        };
+        match blocks.last_mut() {
+            Some((_, last, block)) if *last + 1 == cp && *block == name => {
+                *last = cp;
+            }
+            _ => blocks.push((cp, cp, name)),
+        }
+    }
+
+    blocks.retain(|(first, last, _)| {
+        let first = (*first as usize).min(table.len());
+        let last = (*last as usize + 1).min(table.len());
+        table[first..last].iter().any(|&bits| bits != 0)
+    });
+    Ok(blocks)
+}
+
+/// Run-length encodes the level 1 table as the first `top` index of each run
+/// of identical entries and the level 2 table id they share, but only if that
+/// is smaller than the table itself.
+fn compress_level1(level1_table: &[u16]) -> Option<(Vec<u8>, Vec<u16>)> {
     assert!(
-        runs.len() < u16::MAX as usize,
-        "run table too large for u16 index: {}",
-        runs.len()
+        level1_table.len() <= u8::MAX as usize + 1,
+        "level 1 index must fit in u8 to be compressed"
     );
+    let mut starts = Vec::new();
+    let mut tables: Vec<u16> = Vec::new();
+    for (top, &id) in level1_table.iter().enumerate() {
+        if tables.last() != Some(&id) {
+            starts.push(top as u8);
+            tables.push(id);
+        }
+    }
+
+    let compressed = size_of_val(&starts[..]) + size_of_val(&tables[..]);
+    (compressed < size_of_val(level1_table)).then_some((starts, tables))
+}
+
+/// Returns the inclusive ranges of codepoints which are start characters in
+/// `table` but aren't letters, and of those which are continue but not start
+/// characters and are marks (`Mn`, `Mc` or `Me`), as the first and last
+/// codepoints of each range. Codepoints without a general category
+/// annotation, which can only become identifier characters through an
+/// override, are treated as neither letters nor marks.
+fn build_general_category_ranges(
+    table: &[u8],
+    data: &DerivedData,
+) -> Result<[Ranges; 2], Box<dyn Error>> {
+    let categories =
+        unicode_id_trie_rle_derived_core_properties::parse_general_categories(
+            data.text.as_bytes(),
+        )?;
+
+    let mut non_letter_starts: Ranges = (Vec::new(), Vec::new());
+    let mut mark_continues: Ranges = (Vec::new(), Vec::new());
+    for (cp, &bits) in table.iter().enumerate() {
+        let cp = cp as u32;
+        let category = categories.get(&cp).map(String::as_str);
+        let ranges = if bits & 1 != 0 {
+            if category.is_some_and(|x| x.starts_with('L')) {
+                continue;
+            }
+            &mut non_letter_starts
+        } else if bits & 2 != 0 && category.is_some_and(|x| x.starts_with('M'))
+        {
+            &mut mark_continues
+        } else {
+            continue;
+        };
+
+        let (firsts, lasts) = ranges;
+        if lasts.last().is_some_and(|&last| last + 1 == cp) {
+            *lasts.last_mut().expect("checked above") = cp;
+        } else {
+            firsts.push(cp);
+            lasts.push(cp);
+        }
+    }
+
+    Ok([non_letter_starts, mark_continues])
+}
+
+/// Returns the inclusive ranges of codepoints which are identifier characters
+/// in `table` and have the `Default_Ignorable_Code_Point` property, as the
+/// first and last codepoints of each range. ZWNJ and ZWJ are left out, since
+/// UAX #31 allows them in the middle of identifiers.
+fn build_default_ignorables(
+    table: &[u8],
+    data: &DerivedData,
+) -> (Vec<u32>, Vec<u32>) {
+    let mut firsts: Vec<u32> = Vec::new();
+    let mut lasts: Vec<u32> = Vec::new();
+    for (cp, &bits) in table.iter().enumerate() {
+        let cp = cp as u32;
+        let ignorable = data
+            .properties
+            .get(&cp)
+            .is_some_and(|x| x.contains("Default_Ignorable_Code_Point"));
+        if bits == 0 || !ignorable || cp == 0x200c || cp == 0x200d {
+            continue;
+        }
 
-    let block_count = (MAX_CODEPOINT >> SHIFT) + 1;
-    let block_index = build_block_index(&runs, block_count);
+        if lasts.last().is_some_and(|&last| last + 1 == cp) {
+            *lasts.last_mut().expect("checked above") = cp;
+        } else {
+            firsts.push(cp);
+            lasts.push(cp);
+        }
+    }
+
+    (firsts, lasts)
+}
+
+/// Writes `ascii_class_match`, which classifies ASCII codepoints with a
+/// `match` over the runs of `ascii` rather than a table load.
+fn emit_ascii_match(
+    writer: &mut BufWriter<File>,
+    ascii: &[u8],
+) -> Result<(), Box<dyn Error>> {
+    writeln!(writer, "#[inline(always)]")?;
+    writeln!(
+        writer,
+        "pub(crate) const fn ascii_class_match(cp: u32) -> u8 {{"
+    )?;
+    writeln!(writer, "\tmatch cp {{")?;
+    let mut start = 0;
+    for cp in 1..=ascii.len() {
+        if cp < ascii.len() && ascii[cp] == ascii[start] {
+            continue;
+        }
+        if ascii[start] != 0 {
+            writeln!(
+                writer,
+                "\t\t0x{start:02x}..=0x{:02x} => {},",
+                cp - 1,
+                ascii[start]
+            )?;
+        }
+        start = cp;
+    }
+    writeln!(writer, "\t\t_ => 0,")?;
+    writeln!(writer, "\t}}")?;
+    writeln!(writer, "}}")?;
+    Ok(())
+}
+
+/// The first and last codepoints of each of a set of inclusive ranges.
+type Ranges = (Vec<u32>, Vec<u32>);
+
+/// Returns the inclusive ranges of codepoints with the `Other_ID_Start` and
+/// `Other_ID_Continue` properties, in that order, from the `PropList.txt`
+/// named by `UNICODE_PROPLIST_PATH`, or the bundled one.
+fn build_other_ids() -> Result<[Ranges; 2], Box<dyn Error>> {
+    let path = data_path("UNICODE_PROPLIST_PATH", "PropList.txt")?;
+    println!("cargo:rerun-if-changed={}", path.display());
+
+    // PropList.txt has the same format as DerivedCoreProperties.txt.
+    let parsed = unicode_id_trie_rle_derived_core_properties::parse(
+        File::open(&path)?,
+    )?;
+    Ok(["Other_ID_Start", "Other_ID_Continue"].map(|property| {
+        let mut firsts: Vec<u32> = Vec::new();
+        let mut lasts: Vec<u32> = Vec::new();
+        for (&cp, props) in &parsed {
+            if !props.contains(property) {
+                continue;
+            }
+
+            if lasts.last().is_some_and(|&last| last + 1 == cp) {
+                *lasts.last_mut().expect("checked above") = cp;
+            } else {
+                firsts.push(cp);
+                lasts.push(cp);
+            }
+        }
+        (firsts, lasts)
+    }))
+}
+
+/// Emits a `VersionedTable` for each `DerivedCoreProperties.txt` in the
+/// directory named by `UNICODE_VERSIONS_DIR`, if set, other than one for the
+/// version of the main tables, `builtin`. These are always built with the
+/// default `SHIFT` and `TOP_BITS`, without the overrides.
+fn emit_versioned_tables(
+    writer: &mut BufWriter<File>,
+    builtin: (u8, u8, u8),
+) -> Result<(), Box<dyn Error>> {
+    println!("cargo:rerun-if-env-changed=UNICODE_VERSIONS_DIR");
+    let mut paths = Vec::new();
+    if let Some(dir) = env::var_os("UNICODE_VERSIONS_DIR") {
+        println!("cargo:rerun-if-changed={}", dir.to_string_lossy());
+        for entry in fs::read_dir(dir)? {
+            paths.push(entry?.path());
+        }
+    }
+    paths.sort();
+
+    let mut versions = Vec::new();
+    for path in &paths {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let version = data_version(path)?;
+        if version == builtin {
+            continue;
+        }
+        if versions.iter().any(|(x, _)| *x == version) {
+            return Err(format!(
+                "UNICODE_VERSIONS_DIR has more than one file for {}.{}.{}",
+                version.0, version.1, version.2
+            )
+            .into());
+        }
+
+        let (table, _) = parse_table(path)?;
+        unicode_id_trie_rle_derived_core_properties::check_start_implies_continue(
+            &table,
+        )?;
+        let runs = build_runs(&table);
+        u16_index("runs", runs.len())?;
+        let trie = build_trie(&runs, SHIFT, TOP_BITS)?;
+        check_trie(&trie, None, &table)?;
+
+        let prefix =
+            format!("VERSION_{}_{}_{}", version.0, version.1, version.2);
+        let (starts, values): (Vec<u16>, Vec<u8>) =
+            trie.leaf_runs.iter().copied().unzip();
+        for (name, data) in [
+            ("LEAF_OFFSETS", &trie.leaf_offsets),
+            ("LEAF_RUN_STARTS", &starts),
+            ("LEVEL2_TABLES", &trie.level2_tables),
+            ("LEVEL1_TABLE", &trie.level1_table),
+        ] {
+            emit_u16_array(
+                writer,
+                &format!("{prefix}_{name}"),
+                data,
+                INDEX_BYTES_PER_LINE / 2,
+            )?;
+        }
+        emit_u8_array(
+            writer,
+            &format!("{prefix}_LEAF_RUN_VALUES"),
+            &values,
+            BYTES_PER_LINE,
+        )?;
+        versions.push((version, trie.lower_bits));
+    }
+
+    writeln!(
+        writer,
+        "pub(crate) static VERSIONED_TABLES: [VersionedTable; {}] = [",
+        versions.len()
+    )?;
+    for (version, lower_bits) in &versions {
+        let prefix =
+            format!("VERSION_{}_{}_{}", version.0, version.1, version.2);
+        writeln!(writer, "\tVersionedTable {{")?;
+        writeln!(writer, "\t\tversion: {version:?},")?;
+        writeln!(writer, "\t\ttable: IdentifierTable::new(")?;
+        writeln!(writer, "\t\t\t{SHIFT},")?;
+        writeln!(writer, "\t\t\t{lower_bits},")?;
+        for name in [
+            "LEVEL1_TABLE",
+            "LEVEL2_TABLES",
+            "LEAF_OFFSETS",
+            "LEAF_RUN_STARTS",
+            "LEAF_RUN_VALUES",
+        ] {
+            writeln!(writer, "\t\t\t&{prefix}_{name},")?;
+        }
+        writeln!(writer, "\t\t),")?;
+        writeln!(writer, "\t}},")?;
+    }
+    writeln!(writer, "];")?;
+    Ok(())
+}
+
+const PHF_BUCKET_MULTIPLIER: u32 = 0x9e37_79b1;
+const PHF_SLOT_MULTIPLIER: u32 = 0x85eb_ca6b;
+
+/// Places buckets from largest to smallest, searching for a displacement which
+/// maps every codepoint in the bucket to a distinct free slot. Empty slots
+/// have the key `u32::MAX`, which is never a codepoint.
+fn build_singleton_hash(
+    runs: &[(u32, u8)],
+) -> Result<SingletonHash, Box<dyn Error>> {
+    let singletons: Vec<(u32, u8)> = runs
+        .windows(2)
+        .filter(|pair| pair[1].0 - pair[0].0 == 1)
+        .map(|pair| pair[0])
+        .collect();
+    let min_bits = singletons.len().max(2).next_power_of_two().ilog2();
+
+    for slot_bits in min_bits..min_bits + 4 {
+        let bucket_bits = slot_bits - 1;
+        let mut buckets = vec![Vec::new(); 1 << bucket_bits];
+        for &(cp, value) in &singletons {
+            let bucket =
+                cp.wrapping_mul(PHF_BUCKET_MULTIPLIER) >> (32 - bucket_bits);
+            buckets[bucket as usize].push((cp, value));
+        }
+        let mut order: Vec<usize> = (0..buckets.len()).collect();
+        order.sort_by_key(|&b| std::cmp::Reverse(buckets[b].len()));
+
+        let mut displacements = vec![0u16; buckets.len()];
+        let mut keys = vec![u32::MAX; 1 << slot_bits];
+        let mut values = vec![0u8; 1 << slot_bits];
+        let placed = order.iter().all(|&bucket| {
+            (0..=u16::MAX).any(|displacement| {
+                let slots: Vec<usize> = buckets[bucket]
+                    .iter()
+                    .map(|&(cp, _)| {
+                        ((cp ^ displacement as u32)
+                            .wrapping_mul(PHF_SLOT_MULTIPLIER)
+                            >> (32 - slot_bits))
+                            as usize
+                    })
+                    .collect();
+                let free = slots.iter().enumerate().all(|(i, &slot)| {
+                    keys[slot] == u32::MAX && !slots[..i].contains(&slot)
+                });
+                if free {
+                    for (&slot, &(cp, value)) in
+                        slots.iter().zip(&buckets[bucket])
+                    {
+                        keys[slot] = cp;
+                        values[slot] = value;
+                    }
+                    displacements[bucket] = displacement;
+                }
+                free
+            })
+        });
+
+        if placed {
+            return Ok(SingletonHash {
+                bucket_bits,
+                slot_bits,
+                displacements,
+                keys,
+                values,
+            });
+        }
+    }
+
+    Err(format!(
+        "no perfect hash found for {} singleton runs",
+        singletons.len()
+    )
+    .into())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("cargo:rustc-check-cfg=cfg(compressed_level1)");
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    let out_path = out_dir.join("table.rs");
+
+    let cache = table_cache_paths()?;
+    if let Some((table, cfgs)) = &cache
+        && table.exists()
+        && cfgs.exists()
+    {
+        fs::copy(table, &out_path)?;
+        for cfg in fs::read_to_string(cfgs)?.lines() {
+            println!("cargo:rustc-cfg={cfg}");
+        }
+        return Ok(());
+    }
+
+    let cfgs = generate(&out_path)?;
+    for cfg in &cfgs {
+        println!("cargo:rustc-cfg={cfg}");
+    }
+
+    if let Some((table, cfg_path)) = &cache {
+        // write to temporary files first, so a concurrent build never sees
+        // a partially written entry.
+        let tmp = |path: &Path| {
+            let mut name = path.as_os_str().to_owned();
+            name.push(format!(".tmp{}", std::process::id()));
+            PathBuf::from(name)
+        };
+        let (table_tmp, cfgs_tmp) = (tmp(table), tmp(cfg_path));
+        fs::copy(&out_path, &table_tmp)?;
+        fs::write(&cfgs_tmp, cfgs.join("\n"))?;
+        fs::rename(&table_tmp, table)?;
+        fs::rename(&cfgs_tmp, cfg_path)?;
+    }
+
+    Ok(())
+}
+
+/// Returns where the generated table and the cfgs it needs are cached, if
+/// `UNICODE_TABLE_CACHE_DIR` is set. The file names are a hash of everything
+/// which affects the generated code: this build script, the version and
+/// source of the parser crate, the data files it reads (including every file
+/// in `UNICODE_VERSIONS_DIR`) and the enabled features.
+fn table_cache_paths() -> Result<Option<(PathBuf, PathBuf)>, Box<dyn Error>> {
+    println!("cargo:rerun-if-env-changed=UNICODE_TABLE_CACHE_DIR");
+    let Some(dir) = env::var_os("UNICODE_TABLE_CACHE_DIR") else {
+        return Ok(None);
+    };
+    let dir = PathBuf::from(dir);
+    fs::create_dir_all(&dir)?;
+
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?);
+    let mut hasher = DefaultHasher::new();
+    for path in [
+        "build.rs",
+        "DerivedCoreProperties.txt",
+        "Blocks.txt",
+        "PropList.txt",
+    ] {
+        let path = manifest_dir.join(path);
+        println!("cargo:rerun-if-changed={}", path.display());
+        fs::read(&path)?.hash(&mut hasher);
+    }
+    unicode_id_trie_rle_derived_core_properties::VERSION.hash(&mut hasher);
+    unicode_id_trie_rle_derived_core_properties::SOURCE_HASH.hash(&mut hasher);
+
+    for var in [
+        "UNICODE_OVERRIDES_PATH",
+        "UNICODE_BLOCKS_PATH",
+        "UNICODE_PROPLIST_PATH",
+        "UNICODE_SCRIPTS_PATH",
+    ] {
+        println!("cargo:rerun-if-env-changed={var}");
+        let path = env::var_os(var).map(PathBuf::from);
+        path.is_some().hash(&mut hasher);
+        if let Some(path) = path {
+            println!("cargo:rerun-if-changed={}", path.display());
+            fs::read(&path)?.hash(&mut hasher);
+        }
+    }
+
+    included_scripts().hash(&mut hasher);
+
+    println!("cargo:rerun-if-env-changed=UNICODE_VERSIONS_DIR");
+    if let Some(dir) = env::var_os("UNICODE_VERSIONS_DIR") {
+        // the directory itself, so adding or removing a file reruns this.
+        println!("cargo:rerun-if-changed={}", dir.to_string_lossy());
+        let mut paths = fs::read_dir(&dir)?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<_>, std::io::Error>>()?;
+        paths.sort();
+        for path in paths {
+            println!("cargo:rerun-if-changed={}", path.display());
+            fs::read(&path)?.hash(&mut hasher);
+        }
+    }
+
+    let mut features: Vec<String> = env::vars()
+        .map(|(key, _)| key)
+        .filter(|key| key.starts_with("CARGO_FEATURE_"))
+        .collect();
+    features.sort();
+    features.hash(&mut hasher);
+
+    let key = format!("{:016x}", hasher.finish());
+    Ok(Some((
+        dir.join(format!("{key}.rs")),
+        dir.join(format!("{key}.cfg")),
+    )))
+}
+
+/// The trie levels built from the runs for one choice of `SHIFT` and
+/// `TOP_BITS`.
+struct Trie {
+    shift: u32,
+    top_bits: u32,
+    block_count: u32,
+    lower_bits: u32,
+    leaf_offsets: Vec<u16>,
+    leaf_runs: Vec<(u16, u8)>,
+    level2_tables: Vec<u16>,
+    level1_table: Vec<u16>,
+}
+
+impl Trie {
+    /// The number of bytes the trie levels take up once emitted, not counting
+    /// the tables which don't depend on the shape of the trie.
+    fn table_bytes(&self, level1_rle: bool) -> usize {
+        let level1 = level1_rle
+            .then(|| compress_level1(&self.level1_table))
+            .flatten()
+            .map_or(
+                size_of_val(&self.level1_table[..]),
+                |(starts, tables)| {
+                    size_of_val(&starts[..]) + size_of_val(&tables[..])
+                },
+            );
+        size_of_val(&self.leaf_offsets[..])
+            + self.leaf_runs.len() * (size_of::<u16>() + size_of::<u8>())
+            + size_of_val(&self.level2_tables[..])
+            + level1
+    }
+
+    /// Looks `cp` up the same way the crate does, but with a linear scan of
+    /// the leaf, given the compressed level 1 table if one is emitted.
+    fn decode(
+        &self,
+        level1_runs: Option<&(Vec<u8>, Vec<u16>)>,
+        cp: u32,
+    ) -> u8 {
+        let block = cp >> self.shift;
+        let top = (block >> self.lower_bits) as usize;
+        let bottom = (block & ((1 << self.lower_bits) - 1)) as usize;
+        let level2_idx = match level1_runs {
+            Some((starts, tables)) => {
+                let run =
+                    starts.iter().rposition(|&start| start as usize <= top);
+                tables[run.expect("the first level 1 run starts at 0")]
+            }
+            None => self.level1_table[top],
+        } as usize;
+        let leaf = self.level2_tables[(level2_idx << self.lower_bits) + bottom]
+            as usize;
+        let start = self.leaf_offsets[leaf] as usize;
+        let end = self.leaf_offsets[leaf + 1] as usize;
+        let offset = (cp & ((1 << self.shift) - 1)) as u16;
+        self.leaf_runs[start..end]
+            .iter()
+            .take_while(|(run_start, _)| *run_start <= offset)
+            .last()
+            .map_or(0, |&(_, value)| value)
+    }
+}
+
+/// Decodes every codepoint the trie covers and compares it with `table`, so
+/// a bug in assembling the trie fails the build instead of only the tests.
+fn check_trie(
+    trie: &Trie,
+    level1_runs: Option<&(Vec<u8>, Vec<u16>)>,
+    table: &[u8],
+) -> Result<(), Box<dyn Error>> {
+    for cp in START_CODEPOINT..=MAX_CODEPOINT {
+        let decoded = trie.decode(level1_runs, cp);
+        let expected = table[cp as usize];
+        if decoded != expected {
+            return Err(format!(
+                "the trie decodes U+{cp:04X} as {decoded}, but the data has \
+                 {expected} (SHIFT={}, TOP_BITS={})",
+                trie.shift, trie.top_bits
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+fn build_trie(
+    runs: &[(u32, u8)],
+    shift: u32,
+    top_bits: u32,
+) -> Result<Trie, Box<dyn Error>> {
+    let block_count = (MAX_CODEPOINT >> shift) + 1;
+    let block_index = build_block_index(runs, shift, block_count);
     let block_bits = 32 - (block_count - 1).leading_zeros();
     assert!(
-        block_bits > TOP_BITS,
-        "TOP_BITS ({TOP_BITS}) must be smaller than block bit width ({block_bits})"
+        block_bits > top_bits,
+        "TOP_BITS ({top_bits}) must be smaller than block bit width ({block_bits})"
     );
-    let lower_bits = block_bits - TOP_BITS;
+    let lower_bits = block_bits - top_bits;
     let lower_size = 1usize << lower_bits;
-    let top_size = 1usize << TOP_BITS;
-
+    let top_size = 1usize << top_bits;
     let mut leaf_runs: Vec<(u16, u8)> = Vec::new();
     let mut leaf_offsets: Vec<u16> = Vec::new(); // start index into leaf_runs
     let mut leaf_map: HashMap<Vec<(u16, u8)>, u16> = HashMap::new();
 
     let mut block_to_leaf = Vec::with_capacity(block_count as usize);
     for block in 0..block_count {
-        let block_start = block << SHIFT;
-        let block_end = ((block + 1) << SHIFT).min(MAX_CODEPOINT + 1);
+        let block_start = block << shift;
+        let block_end = ((block + 1) << shift).min(MAX_CODEPOINT + 1);
 
         let mut idx = block_index[block as usize];
         let mut local_runs = Vec::new();
@@ -185,10 +988,9 @@ fn main() -> Result<(), Box<dyn Error>> {
         let leaf_id = if let Some(&id) = leaf_map.get(&local_runs) {
             id
         } else {
-            let id =
-                u16::try_from(leaf_map.len()).expect("leaf count fits in u16");
+            let id = u16_index("leaves", leaf_map.len())?;
             let start = leaf_runs.len();
-            leaf_offsets.push(start as u16);
+            leaf_offsets.push(u16_index("leaf runs", start)?);
             leaf_runs.extend_from_slice(&local_runs);
             leaf_map.insert(local_runs.clone(), id);
             id
@@ -196,24 +998,22 @@ fn main() -> Result<(), Box<dyn Error>> {
 
         block_to_leaf.push(leaf_id);
     }
-    leaf_offsets.push(leaf_runs.len() as u16); // sentinel for computing leaf lengths
+    // sentinel for computing leaf lengths
+    leaf_offsets.push(u16_index("leaf runs", leaf_runs.len())?);
 
     let mut level2_map: HashMap<Vec<u16>, u16> = HashMap::new();
     let mut level2_tables: Vec<u16> = Vec::new();
     let mut level1_table = Vec::with_capacity(top_size);
 
     for top in 0..top_size {
-        let mut table = vec![0u16; lower_size];
-        for low in 0..lower_size {
-            let block = (top << lower_bits) | low;
-            table[low] = block_to_leaf[block];
-        }
+        let first_block = top << lower_bits;
+        let table =
+            block_to_leaf[first_block..first_block + lower_size].to_vec();
 
         let table_id = if let Some(&id) = level2_map.get(&table) {
             id
         } else {
-            let id = u16::try_from(level2_map.len())
-                .expect("level2 table count fits in u16");
+            let id = u16_index("level 2 tables", level2_map.len())?;
             level2_map.insert(table.clone(), id);
             level2_tables.extend_from_slice(&table);
             id
@@ -221,6 +1021,87 @@ fn main() -> Result<(), Box<dyn Error>> {
         level1_table.push(table_id);
     }
 
+    Ok(Trie {
+        shift,
+        top_bits,
+        block_count,
+        lower_bits,
+        leaf_offsets,
+        leaf_runs,
+        level2_tables,
+        level1_table,
+    })
+}
+
+/// Builds the trie for every shape in `AUTO_SHIFTS` and `AUTO_TOP_BITS`,
+/// reporting each size as a warning, and returns the smallest.
+fn search_trie_shape(runs: &[(u32, u8)]) -> Result<Trie, Box<dyn Error>> {
+    let level1_rle = env::var_os("CARGO_FEATURE_LEVEL1_RLE").is_some();
+    let mut best: Option<(usize, Trie)> = None;
+    for shift in AUTO_SHIFTS {
+        for top_bits in AUTO_TOP_BITS {
+            let trie = match build_trie(runs, shift, top_bits) {
+                Ok(trie) => trie,
+                Err(err) => {
+                    println!(
+                        "cargo:warning=SHIFT={shift} TOP_BITS={top_bits}: {err}"
+                    );
+                    continue;
+                }
+            };
+            let bytes = trie.table_bytes(level1_rle);
+            println!(
+                "cargo:warning=SHIFT={shift} TOP_BITS={top_bits}: {bytes} bytes"
+            );
+            if best.as_ref().is_none_or(|(smallest, _)| bytes < *smallest) {
+                best = Some((bytes, trie));
+            }
+        }
+    }
+
+    let (bytes, trie) = best.ok_or("no trie shape fits in the tables")?;
+    println!(
+        "cargo:warning=chose SHIFT={} TOP_BITS={}: {bytes} bytes",
+        trie.shift, trie.top_bits,
+    );
+    Ok(trie)
+}
+
+/// Generates the tables into `out_path`, returning the cfgs the crate needs
+/// to be compiled with to read them.
+fn generate(out_path: &Path) -> Result<Vec<&'static str>, Box<dyn Error>> {
+    let (table, data) = build_table()?;
+    // Every start character must also be a continue character, otherwise
+    // `str_is_identifier` would reject identifiers starting with it.
+    unicode_id_trie_rle_derived_core_properties::check_start_implies_continue(
+        &table,
+    )?;
+    let runs = build_runs(&table);
+    u16_index("runs", runs.len())?;
+
+    let trie = if env::var_os("CARGO_FEATURE_AUTO_SHIFT").is_some() {
+        search_trie_shape(&runs)?
+    } else {
+        build_trie(&runs, SHIFT, TOP_BITS)?
+    };
+    let level1_runs = if env::var_os("CARGO_FEATURE_LEVEL1_RLE").is_some() {
+        compress_level1(&trie.level1_table)
+    } else {
+        None
+    };
+    check_trie(&trie, level1_runs.as_ref(), &table)?;
+    let Trie {
+        shift,
+        block_count,
+        lower_bits,
+        leaf_offsets,
+        leaf_runs,
+        level2_tables,
+        level1_table,
+        ..
+    } = trie;
+    let lower_size = 1usize << lower_bits;
+
     let mut offsets = Vec::with_capacity(leaf_runs.len());
     let mut values = Vec::with_capacity(leaf_runs.len());
     for (start, value) in &leaf_runs {
@@ -228,13 +1109,12 @@ fn main() -> Result<(), Box<dyn Error>> {
         values.push(*value);
     }
 
-    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
-    let out_path = out_dir.join("table.rs");
-    let out_file = File::create(&out_path)?;
+    let mut cfgs = Vec::new();
+    let out_file = File::create(out_path)?;
     let mut writer = BufWriter::new(out_file);
 
     writeln!(writer, "// Code generated by build.rs; DO NOT EDIT.")?;
-    writeln!(writer, "pub(crate) const SHIFT: u32 = {SHIFT};")?;
+    writeln!(writer, "pub(crate) const SHIFT: u32 = {shift};")?;
     writeln!(
         writer,
         "pub(crate) const BLOCK_COUNT: usize = {};",
@@ -242,6 +1122,19 @@ fn main() -> Result<(), Box<dyn Error>> {
     )?;
     writeln!(writer, "pub(crate) const LOWER_BITS: u32 = {lower_bits};")?;
     writeln!(writer, "pub(crate) const LOWER_SIZE: usize = {lower_size};")?;
+    writeln!(
+        writer,
+        "pub(crate) const FINGERPRINT: u64 = 0x{:016x};",
+        identifier_set_fingerprint(&table)
+    )?;
+    writeln!(
+        writer,
+        "pub(crate) const INCLUDE_SCRIPTS: Option<&[&str]> = {};",
+        match included_scripts() {
+            Some(scripts) => format!("Some(&{scripts:?})"),
+            None => "None".to_string(),
+        }
+    )?;
 
     emit_u16_array(
         &mut writer,
@@ -249,26 +1142,182 @@ fn main() -> Result<(), Box<dyn Error>> {
         &leaf_offsets,
         INDEX_BYTES_PER_LINE / 2,
     )?;
-    emit_u16_array(
+    emit_aligned_u16_array(
         &mut writer,
         "LEAF_RUN_STARTS",
         &offsets,
         INDEX_BYTES_PER_LINE / 2,
     )?;
-    emit_u8_array(&mut writer, "LEAF_RUN_VALUES", &values, BYTES_PER_LINE)?;
-    emit_u16_array(
+    emit_aligned_u8_array(
+        &mut writer,
+        "LEAF_RUN_VALUES",
+        &values,
+        BYTES_PER_LINE,
+    )?;
+    emit_aligned_u16_array(
         &mut writer,
         "LEVEL2_TABLES",
         &level2_tables,
         INDEX_BYTES_PER_LINE / 2,
     )?;
-    emit_u16_array(
+
+    if let Some((starts, tables)) = level1_runs {
+        cfgs.push("compressed_level1");
+        emit_u8_array(
+            &mut writer,
+            "LEVEL1_RUN_STARTS",
+            &starts,
+            BYTES_PER_LINE,
+        )?;
+        emit_u16_array(
+            &mut writer,
+            "LEVEL1_RUN_TABLES",
+            &tables,
+            INDEX_BYTES_PER_LINE / 2,
+        )?;
+    } else {
+        emit_u16_array(
+            &mut writer,
+            "LEVEL1_TABLE",
+            &level1_table,
+            INDEX_BYTES_PER_LINE / 2,
+        )?;
+    }
+
+    if env::var_os("CARGO_FEATURE_GENERAL_CATEGORY").is_some() {
+        let [non_letter_starts, mark_continues] =
+            build_general_category_ranges(&table, &data)?;
+        for (name, ranges) in [
+            ("NON_LETTER_START", non_letter_starts),
+            ("MARK_CONTINUE", mark_continues),
+        ] {
+            emit_u32_array(
+                &mut writer,
+                &format!("{name}_FIRSTS"),
+                &ranges.0,
+                INDEX_BYTES_PER_LINE / 4,
+            )?;
+            emit_u32_array(
+                &mut writer,
+                &format!("{name}_LASTS"),
+                &ranges.1,
+                INDEX_BYTES_PER_LINE / 4,
+            )?;
+        }
+    }
+
+    let (firsts, lasts) = build_default_ignorables(&table, &data);
+    emit_u32_array(
         &mut writer,
-        "LEVEL1_TABLE",
-        &level1_table,
-        INDEX_BYTES_PER_LINE / 2,
+        "DEFAULT_IGNORABLE_FIRSTS",
+        &firsts,
+        INDEX_BYTES_PER_LINE / 4,
+    )?;
+    emit_u32_array(
+        &mut writer,
+        "DEFAULT_IGNORABLE_LASTS",
+        &lasts,
+        INDEX_BYTES_PER_LINE / 4,
+    )?;
+
+    if env::var_os("CARGO_FEATURE_ASCII_MATCH").is_some() {
+        emit_ascii_match(&mut writer, &table[..START_CODEPOINT as usize])?;
+    }
+
+    // The runs start at START_CODEPOINT, so the trie doesn't cover ASCII.
+    // The tests check the hand-written ASCII_TABLE against these instead.
+    writeln!(writer, "#[cfg(test)]")?;
+    emit_u8_array(
+        &mut writer,
+        "DATA_ASCII_CLASSES",
+        &table[..START_CODEPOINT as usize],
+        INDEX_BYTES_PER_LINE,
+    )?;
+
+    if env::var_os("CARGO_FEATURE_OTHER_ID").is_some() {
+        let [start, cont] = build_other_ids()?;
+        for (name, ranges) in
+            [("OTHER_ID_START", start), ("OTHER_ID_CONTINUE", cont)]
+        {
+            emit_u32_array(
+                &mut writer,
+                &format!("{name}_FIRSTS"),
+                &ranges.0,
+                INDEX_BYTES_PER_LINE / 4,
+            )?;
+            emit_u32_array(
+                &mut writer,
+                &format!("{name}_LASTS"),
+                &ranges.1,
+                INDEX_BYTES_PER_LINE / 4,
+            )?;
+        }
+    }
+
+    if env::var_os("CARGO_FEATURE_BLOCKS").is_some() {
+        let blocks = build_identifier_blocks(&table)?;
+        writeln!(
+            writer,
+            "pub(crate) static IDENTIFIER_BLOCKS_TABLE: [(RangeInclusive<u32>, &str); {}] = [",
+            blocks.len()
+        )?;
+        for (first, last, name) in &blocks {
+            writeln!(writer, "\t(0x{first:04x}..=0x{last:04x}, {name:?}),")?;
+        }
+        writeln!(writer, "];")?;
+    }
+
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?);
+    let builtin =
+        data_version(&manifest_dir.join("./DerivedCoreProperties.txt"))?;
+    writeln!(
+        writer,
+        "pub(crate) const DATA_VERSION: (u8, u8, u8) = {builtin:?};"
     )?;
+    if env::var_os("CARGO_FEATURE_VERSIONED").is_some() {
+        emit_versioned_tables(&mut writer, builtin)?;
+    }
+
+    if env::var_os("CARGO_FEATURE_PHF").is_some() {
+        let hash = build_singleton_hash(&runs)?;
+        writeln!(
+            writer,
+            "pub(crate) const PHF_BUCKET_MULTIPLIER: u32 = 0x{PHF_BUCKET_MULTIPLIER:08x};"
+        )?;
+        writeln!(
+            writer,
+            "pub(crate) const PHF_SLOT_MULTIPLIER: u32 = 0x{PHF_SLOT_MULTIPLIER:08x};"
+        )?;
+        writeln!(
+            writer,
+            "pub(crate) const PHF_BUCKET_BITS: u32 = {};",
+            hash.bucket_bits
+        )?;
+        writeln!(
+            writer,
+            "pub(crate) const PHF_SLOT_BITS: u32 = {};",
+            hash.slot_bits
+        )?;
+        emit_u16_array(
+            &mut writer,
+            "PHF_DISPLACEMENTS",
+            &hash.displacements,
+            INDEX_BYTES_PER_LINE / 2,
+        )?;
+        emit_u32_array(
+            &mut writer,
+            "PHF_KEYS",
+            &hash.keys,
+            INDEX_BYTES_PER_LINE / 4,
+        )?;
+        emit_u8_array(
+            &mut writer,
+            "PHF_VALUES",
+            &hash.values,
+            BYTES_PER_LINE,
+        )?;
+    }
 
     writer.flush()?;
-    Ok(())
+    Ok(cfgs)
 }
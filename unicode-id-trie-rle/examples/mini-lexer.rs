@@ -0,0 +1,71 @@
+//! Tokenizes a toy language into identifiers, numbers and punctuation, showing
+//! how [identifier_len] splits identifiers off the front of the input and how
+//! [identifier_spans] finds every identifier in a line.
+//!
+//! ```text
+//! cargo run -p unicode-id-trie-rle --example mini-lexer -- 'größe = x1 + 42'
+//! ```
+
+use std::env;
+
+use unicode_id_trie_rle::{identifier_len, identifier_spans};
+
+const DEFAULT_SOURCE: &str = "let größe = (x1 + 42) * 名前_2;";
+
+enum Token<'a> {
+    Identifier(&'a str),
+    Number(&'a str),
+    Punctuation(char),
+}
+
+fn tokenize(mut s: &str) -> Result<Vec<Token<'_>>, String> {
+    let mut tokens = Vec::new();
+    loop {
+        s = s.trim_start();
+        let Some(c) = s.chars().next() else {
+            return Ok(tokens);
+        };
+
+        let len = identifier_len(s);
+        let (token, len) = if len > 0 {
+            (Token::Identifier(&s[..len]), len)
+        } else if c.is_ascii_digit() {
+            let len = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+            (Token::Number(&s[..len]), len)
+        } else if "=+-*/();".contains(c) {
+            (Token::Punctuation(c), c.len_utf8())
+        } else {
+            return Err(format!("unexpected character {c:?}"));
+        };
+
+        tokens.push(token);
+        s = &s[len..];
+    }
+}
+
+fn main() {
+    let source = env::args().nth(1);
+    let source = source.as_deref().unwrap_or(DEFAULT_SOURCE);
+    println!("source: {source}");
+
+    match tokenize(source) {
+        Ok(tokens) => {
+            for token in tokens {
+                match token {
+                    Token::Identifier(x) => println!("  identifier  {x}"),
+                    Token::Number(x) => println!("  number      {x}"),
+                    Token::Punctuation(x) => println!("  punctuation {x}"),
+                }
+            }
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+    }
+
+    let identifiers = identifier_spans(source)
+        .map(|x| &source[x])
+        .collect::<Vec<_>>();
+    println!("identifiers: {}", identifiers.join(", "));
+}
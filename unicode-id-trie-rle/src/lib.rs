@@ -1,16 +1,157 @@
 #![doc = include_str!("../README.md")]
 #![cfg_attr(not(test), no_std)]
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+use alloc::borrow::Cow;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::ops::{Range, RangeInclusive};
+
 const IDENTIFIER_OTHER: u8 = 0;
 const IDENTIFIER_START: u8 = 1;
 const IDENTIFIER_CONTINUE: u8 = 2;
 const START_CODEPOINT: u32 = 0x80;
+const SURROGATE_START: u32 = 0xD800;
+const SURROGATE_END: u32 = 0xDFFF;
 
 include!(concat!(env!("OUT_DIR"), "/table.rs"));
 
+/// Aligns the generated tables which vectorized lookups load from, so they
+/// can use aligned loads.
+#[repr(C, align(16))]
+struct Aligned16<T>(T);
+
 const BLOCK_MASK: u32 = (1 << SHIFT) - 1;
 const LOWER_MASK: u32 = (1 << LOWER_BITS) - 1;
 const ASCII_TABLE: [u8; 128] = ascii_table();
 
+/// The number of bytes of static storage used by the lookup tables, including
+/// the ASCII fast path table.
+pub const TABLE_BYTES: usize = size_of_val(&LEAF_OFFSETS)
+    + size_of_val(LEAF_RUN_STARTS)
+    + size_of_val(LEAF_RUN_VALUES)
+    + size_of_val(LEVEL2_TABLES)
+    + LEVEL1_BYTES
+    + size_of_val(&ASCII_TABLE)
+    + size_of_val(&DEFAULT_IGNORABLE_FIRSTS)
+    + size_of_val(&DEFAULT_IGNORABLE_LASTS)
+    + PHF_BYTES
+    + GENERAL_CATEGORY_BYTES
+    + OTHER_ID_BYTES
+    + VERSIONED_BYTES;
+
+/// The number of distinct leaves in the trie, after deduplication.
+pub const LEAF_COUNT: usize = LEAF_OFFSETS.len() - 1;
+
+/// The number of runs across every leaf, not counting the sentinel which ends
+/// each leaf.
+pub const RUN_COUNT: usize = LEAF_RUN_STARTS.len() - LEAF_COUNT;
+
+/// The number of distinct level 2 tables, after deduplication.
+pub const LEVEL2_TABLE_COUNT: usize = LEVEL2_TABLES.len() / LOWER_SIZE;
+
+/// A fingerprint of which codepoints are start and continue characters,
+/// including any overrides applied at build time.
+///
+/// This only changes when the classification of some codepoint does, not
+/// when the data file changes cosmetically or the tables are laid out
+/// differently, so it can key caches of anything derived from the set of
+/// valid identifiers.
+pub const IDENTIFIER_SET_FINGERPRINT: u64 = FINGERPRINT;
+
+/// The scripts the tables were restricted to by setting
+/// `UNICODE_INCLUDE_SCRIPTS` at build time, or `None` if they cover every
+/// script.
+///
+/// When this is `Some`, every codepoint outside the listed scripts is
+/// classified as neither start nor continue, so the tables aren't UAX #31
+/// compliant. See the README.
+pub const INCLUDED_SCRIPTS: Option<&[&str]> = INCLUDE_SCRIPTS;
+
+/// The structural counts of the lookup tables, as returned by
+/// [structure_summary].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Summary {
+    /// See [LEAF_COUNT].
+    pub leaves: usize,
+    /// See [RUN_COUNT].
+    pub runs: usize,
+    /// See [LEVEL2_TABLE_COUNT].
+    pub level2_tables: usize,
+    /// See [TABLE_BYTES].
+    pub table_bytes: usize,
+}
+
+/// Returns the structural counts of the lookup tables, which is useful for
+/// comparing encodings alongside [TABLE_BYTES].
+pub const fn structure_summary() -> Summary {
+    Summary {
+        leaves: LEAF_COUNT,
+        runs: RUN_COUNT,
+        level2_tables: LEVEL2_TABLE_COUNT,
+        table_bytes: TABLE_BYTES,
+    }
+}
+
+#[cfg(not(compressed_level1))]
+const LEVEL1_BYTES: usize = size_of_val(&LEVEL1_TABLE);
+#[cfg(compressed_level1)]
+const LEVEL1_BYTES: usize =
+    size_of_val(&LEVEL1_RUN_STARTS) + size_of_val(&LEVEL1_RUN_TABLES);
+
+/// Every Unicode block, as listed in `Blocks.txt`, which contains at least one
+/// `XID_Start` or `XID_Continue` codepoint, in ascending order along with its
+/// name.
+///
+/// This is built from the `Blocks.txt` bundled with this crate, or the one
+/// named by `UNICODE_BLOCKS_PATH` at build time if it's set.
+#[cfg(feature = "blocks")]
+pub const IDENTIFIER_BLOCKS: &[(RangeInclusive<u32>, &str)] =
+    &IDENTIFIER_BLOCKS_TABLE;
+
+#[cfg(feature = "phf")]
+const PHF_BYTES: usize = size_of_val(&PHF_DISPLACEMENTS)
+    + size_of_val(&PHF_KEYS)
+    + size_of_val(&PHF_VALUES);
+#[cfg(not(feature = "phf"))]
+const PHF_BYTES: usize = 0;
+
+#[cfg(feature = "general_category")]
+const GENERAL_CATEGORY_BYTES: usize = size_of_val(&NON_LETTER_START_FIRSTS)
+    + size_of_val(&NON_LETTER_START_LASTS)
+    + size_of_val(&MARK_CONTINUE_FIRSTS)
+    + size_of_val(&MARK_CONTINUE_LASTS);
+#[cfg(not(feature = "general_category"))]
+const GENERAL_CATEGORY_BYTES: usize = 0;
+
+#[cfg(feature = "versioned")]
+const VERSIONED_BYTES: usize = {
+    let mut bytes = size_of_val(&VERSIONED_TABLES);
+    let mut i = 0;
+    while i < VERSIONED_TABLES.len() {
+        bytes += VERSIONED_TABLES[i].table.bytes();
+        i += 1;
+    }
+    bytes
+};
+#[cfg(not(feature = "versioned"))]
+const VERSIONED_BYTES: usize = 0;
+
+#[cfg(feature = "other_id")]
+const OTHER_ID_BYTES: usize = size_of_val(&OTHER_ID_START_FIRSTS)
+    + size_of_val(&OTHER_ID_START_LASTS)
+    + size_of_val(&OTHER_ID_CONTINUE_FIRSTS)
+    + size_of_val(&OTHER_ID_CONTINUE_LASTS);
+#[cfg(not(feature = "other_id"))]
+const OTHER_ID_BYTES: usize = 0;
+
 #[derive(Clone, Copy)]
 struct Leaf {
     offset: usize,
@@ -23,6 +164,16 @@ struct Leaf {
 pub struct UnicodeIdentifierClass(u8);
 
 impl UnicodeIdentifierClass {
+    /// Returns the class with exactly the given properties. This is useful
+    /// for describing classes computed elsewhere, such as from the Unicode
+    /// data itself, with the same API.
+    #[inline]
+    pub const fn new(is_start: bool, is_continue: bool) -> Self {
+        let start = if is_start { IDENTIFIER_START } else { 0 };
+        let cont = if is_continue { IDENTIFIER_CONTINUE } else { 0 };
+        Self(start | cont)
+    }
+
     /// Returns whether or not the codepoint was one of the `*_Start`
     /// identifiers.
     #[inline]
@@ -36,52 +187,513 @@ impl UnicodeIdentifierClass {
     pub fn is_continue(&self) -> bool {
         self.0 & IDENTIFIER_CONTINUE != 0
     }
-}
 
-#[inline]
-fn load_leaf(idx: usize) -> Leaf {
-    debug_assert!(idx + 1 < LEAF_OFFSETS.len());
-    let start = LEAF_OFFSETS[idx] as usize;
-    let end = LEAF_OFFSETS[idx + 1] as usize;
-    Leaf {
-        offset: start,
-        len: end - start,
+    /// Returns this class without its `*_Start` property, keeping
+    /// `*_Continue`. Once the first character of an identifier has been
+    /// checked, every character after it can be checked the same way, so
+    /// this lets callers treat the first one uniformly too.
+    #[inline]
+    pub fn as_continue(self) -> Self {
+        Self(self.0 & !IDENTIFIER_START)
+    }
+
+    /// Combines two classes into one which has every property either of them
+    /// has. This is useful when combining classifications from multiple
+    /// sources, such as a base table and a set of overrides.
+    ///
+    /// Classes form a partial order where a class with no properties is below
+    /// one with only `*_Continue`, which in turn is below one with both
+    /// `*_Start` and `*_Continue`. `merge` returns the least class which is at
+    /// or above both `a` and `b`, so it is commutative and associative.
+    #[inline]
+    pub fn merge(a: Self, b: Self) -> Self {
+        Self(a.0 | b.0)
     }
 }
 
-#[inline]
-fn leaf_value(leaf: Leaf, offset: u16) -> UnicodeIdentifierClass {
-    debug_assert!(leaf.len >= 2);
-    let runs = &LEAF_RUN_STARTS[leaf.offset..leaf.offset + leaf.len];
-    let values = &LEAF_RUN_VALUES[leaf.offset..leaf.offset + leaf.len];
-    // runs are ascending with runs[0] == 0 and a sentinel at the end.
-    let idx = runs.partition_point(|&start| start <= offset);
-    UnicodeIdentifierClass(values[idx.saturating_sub(1)])
+/// The level 1 table of an [IdentifierTable].
+#[derive(Clone, Copy)]
+enum Level1<'a> {
+    /// The level 2 table id for every `top` index.
+    Table(&'a [u16]),
+    /// Runs of identical entries, as generated by the `level1_rle` feature:
+    /// the `top` index each run starts at and its level 2 table id.
+    #[cfg(compressed_level1)]
+    Runs { starts: &'a [u8], tables: &'a [u16] },
+}
+
+/// The arrays making up the trie described in the README, along with its
+/// shape. [IdentifierTable::classify] looks codepoints up in them.
+///
+/// [STATIC_TABLE] borrows the tables generated for this crate, and
+/// [unicode_identifier_class] is a wrapper around it. Because the table is a
+/// value rather than a set of globals, it can be stored in your own structs,
+/// and [IdentifierTable::new] runs the same lookup over arrays you supply,
+/// such as copies placed in a particular memory section.
+#[derive(Clone, Copy)]
+pub struct IdentifierTable<'a> {
+    shift: u32,
+    lower_bits: u32,
+    level1: Level1<'a>,
+    level2_tables: &'a [u16],
+    leaf_offsets: &'a [u16],
+    leaf_run_starts: &'a [u16],
+    leaf_run_values: &'a [u8],
+}
+
+/// The tables generated for this crate, which [unicode_identifier_class]
+/// looks codepoints up in.
+pub const STATIC_TABLE: IdentifierTable<'static> = IdentifierTable {
+    shift: SHIFT,
+    lower_bits: LOWER_BITS,
+    #[cfg(not(compressed_level1))]
+    level1: Level1::Table(&LEVEL1_TABLE),
+    #[cfg(compressed_level1)]
+    level1: Level1::Runs {
+        starts: &LEVEL1_RUN_STARTS,
+        tables: &LEVEL1_RUN_TABLES,
+    },
+    level2_tables: LEVEL2_TABLES,
+    leaf_offsets: &LEAF_OFFSETS,
+    leaf_run_starts: LEAF_RUN_STARTS,
+    leaf_run_values: LEAF_RUN_VALUES,
+};
+
+// every codepoint below U+100000 has a block in the generated tables.
+const _: () = assert!((0x100000 - 1) >> SHIFT < BLOCK_COUNT as u32);
+
+impl<'a> IdentifierTable<'a> {
+    /// Returns the table made up of the given arrays, laid out the same way
+    /// as the generated ones: codepoints are split into blocks of
+    /// `1 << shift`, and the low `lower_bits` bits of the block index select
+    /// an entry of a level 2 table.
+    ///
+    /// The arrays aren't validated. Every access is bounds checked, so
+    /// inconsistent arrays make lookups panic or return the wrong class, but
+    /// never cause undefined behavior.
+    pub const fn new(
+        shift: u32,
+        lower_bits: u32,
+        level1_table: &'a [u16],
+        level2_tables: &'a [u16],
+        leaf_offsets: &'a [u16],
+        leaf_run_starts: &'a [u16],
+        leaf_run_values: &'a [u8],
+    ) -> Self {
+        Self {
+            shift,
+            lower_bits,
+            level1: Level1::Table(level1_table),
+            level2_tables,
+            leaf_offsets,
+            leaf_run_starts,
+            leaf_run_values,
+        }
+    }
+
+    /// Returns whether `cp` has the properties `XID_Start` or
+    /// `XID_Continue` according to this table.
+    ///
+    /// Codepoints below `U+0080` are always looked up in this crate's ASCII
+    /// table, and codepoints from `U+100000` up are never identifier
+    /// characters, so the arrays only need to cover the rest.
+    #[inline]
+    pub fn classify(&self, cp: char) -> UnicodeIdentifierClass {
+        // ASCII fast path, see ascii_class.
+        if (cp as u32) < START_CODEPOINT {
+            return UnicodeIdentifierClass(ascii_class(cp as u32));
+        }
+
+        if (cp as u32) >= 0x100000 {
+            return UnicodeIdentifierClass(IDENTIFIER_OTHER);
+        }
+
+        let cp = cp as u32;
+        self.leaf_value(self.block_leaf(cp), self.block_offset(cp))
+    }
+
+    /// Returns the offset of `cp` within its block.
+    #[inline]
+    fn block_offset(&self, cp: u32) -> u16 {
+        (cp & ((1 << self.shift) - 1)) as u16
+    }
+
+    #[inline]
+    fn block_leaf(&self, cp: u32) -> Leaf {
+        let block = cp >> self.shift;
+        let top = (block >> self.lower_bits) as usize;
+        let bottom = (block & ((1 << self.lower_bits) - 1)) as usize;
+        let level2_idx = self.level1_entry(top);
+        let leaf_idx = self.level2_tables
+            [(level2_idx << self.lower_bits) + bottom]
+            as usize;
+        self.load_leaf(leaf_idx)
+    }
+
+    /// Finds the level 2 table id for `top`. A compressed level 1 table only
+    /// has a handful of runs, so this is a short search.
+    #[inline]
+    fn level1_entry(&self, top: usize) -> usize {
+        match self.level1 {
+            Level1::Table(table) => table[top] as usize,
+            #[cfg(compressed_level1)]
+            Level1::Runs { starts, tables } => {
+                // the first run always starts at 0, so idx is at least 1.
+                let idx =
+                    starts.partition_point(|&start| start as usize <= top);
+                tables[idx - 1] as usize
+            }
+        }
+    }
+
+    #[inline]
+    fn load_leaf(&self, idx: usize) -> Leaf {
+        debug_assert!(idx + 1 < self.leaf_offsets.len());
+        let start = self.leaf_offsets[idx] as usize;
+        let end = self.leaf_offsets[idx + 1] as usize;
+        Leaf {
+            offset: start,
+            len: end - start,
+        }
+    }
+
+    /// Returns the run starts and values of `leaf`.
+    #[inline]
+    fn leaf_runs(&self, leaf: Leaf) -> (&'a [u16], &'a [u8]) {
+        let range = leaf.offset..leaf.offset + leaf.len;
+        (
+            &self.leaf_run_starts[range.clone()],
+            &self.leaf_run_values[range],
+        )
+    }
+
+    #[inline]
+    fn leaf_value(&self, leaf: Leaf, offset: u16) -> UnicodeIdentifierClass {
+        debug_assert!(leaf.len >= 2);
+        let (runs, values) = self.leaf_runs(leaf);
+        // runs are ascending with runs[0] == 0 and a sentinel at the end.
+        let idx = runs.partition_point(|&start| start <= offset);
+        UnicodeIdentifierClass(values[idx.saturating_sub(1)])
+    }
+
+    /// Returns the number of bytes taken up by the arrays.
+    #[cfg(feature = "versioned")]
+    const fn bytes(&self) -> usize {
+        let level1 = match self.level1 {
+            Level1::Table(table) => size_of_val(table),
+            #[cfg(compressed_level1)]
+            Level1::Runs { starts, tables } => {
+                size_of_val(starts) + size_of_val(tables)
+            }
+        };
+        level1
+            + size_of_val(self.level2_tables)
+            + size_of_val(self.leaf_offsets)
+            + size_of_val(self.leaf_run_starts)
+            + size_of_val(self.leaf_run_values)
+    }
 }
 
 /// Returns whether the codepoint specified has the properties `ID_Start`,
 /// `XID_Start` or the properties `ID_Continue` or `XID_Continue`.
 #[inline]
 pub fn unicode_identifier_class(cp: char) -> UnicodeIdentifierClass {
-    // ASCII fast path via table to avoid unpredictable branches.
+    #[cfg(feature = "phf")]
+    if (START_CODEPOINT..0x100000).contains(&(cp as u32))
+        && let Some(bits) = singleton_class(cp as u32)
+    {
+        return UnicodeIdentifierClass(bits);
+    }
+
+    STATIC_TABLE.classify(cp)
+}
+
+/// Looks `cp` up in the trie, skipping the ASCII table and the short circuit
+/// above `U+100000`, so tests can check what the trie itself holds.
+///
+/// The trie only has blocks from `U+0080` up to `U+100000`, which are all the
+/// build script checks, so this panics for anything else.
+#[cfg(test)]
+pub(crate) fn classify_trie_only(cp: u32) -> UnicodeIdentifierClass {
+    let end = (BLOCK_COUNT as u32) << SHIFT;
+    assert!(
+        (START_CODEPOINT..end).contains(&cp),
+        "U+{cp:04X} isn't covered by the trie"
+    );
+    let table = &STATIC_TABLE;
+    table.leaf_value(table.block_leaf(cp), table.block_offset(cp))
+}
+
+/// Returns whether `c` is classified by the ASCII fast path, meaning it is
+/// below `U+0080`, rather than by the trie. Callers splitting their own work
+/// between ASCII and other text can use this to draw the line in the same
+/// place as this crate.
+///
+/// ```
+/// use unicode_id_trie_rle::is_ascii_fast_path;
+///
+/// assert!(is_ascii_fast_path('a'));
+/// assert!(is_ascii_fast_path('\u{7f}'));
+/// assert!(!is_ascii_fast_path('\u{80}'));
+/// assert!(!is_ascii_fast_path('\u{e9}'));
+/// ```
+#[inline]
+pub const fn is_ascii_fast_path(c: char) -> bool {
+    (c as u32) < START_CODEPOINT
+}
+
+/// Remembers the leaf of the last block looked up, so that consecutive
+/// queries in the same block skip the level 1 and level 2 tables.
+///
+/// This only pays off for sequential access where neighbouring characters
+/// tend to share a block, such as a tokenizer running over text in a single
+/// script. The cache is mutable state, so each thread or input stream needs
+/// its own; for scattered lookups, use [unicode_identifier_class] directly.
+///
+/// ```
+/// use unicode_id_trie_rle::ClassifierCache;
+///
+/// let mut cache = ClassifierCache::new();
+/// assert!("\u{1e900}\u{1e922}".chars().all(|c| cache.classify(c).is_start()));
+/// ```
+#[derive(Clone, Copy)]
+pub struct ClassifierCache {
+    block: u32,
+    leaf: Leaf,
+}
+
+impl ClassifierCache {
+    /// Returns an empty cache.
+    pub const fn new() -> Self {
+        Self {
+            // never a block in the trie, so the first lookup always misses.
+            block: u32::MAX,
+            leaf: Leaf { offset: 0, len: 0 },
+        }
+    }
+
+    /// Returns the same class as [unicode_identifier_class], reusing the
+    /// cached leaf when `cp` is in the same block as the previous query.
+    #[inline]
+    pub fn classify(&mut self, cp: char) -> UnicodeIdentifierClass {
+        let cp = cp as u32;
+        if cp < START_CODEPOINT {
+            return UnicodeIdentifierClass(ascii_class(cp));
+        }
+
+        if cp >= 0x100000 {
+            return UnicodeIdentifierClass(IDENTIFIER_OTHER);
+        }
+
+        let block = cp >> SHIFT;
+        if block != self.block {
+            self.block = block;
+            self.leaf = STATIC_TABLE.block_leaf(cp);
+        }
+        STATIC_TABLE.leaf_value(self.leaf, (cp & BLOCK_MASK) as u16)
+    }
+}
+
+impl Default for ClassifierCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Which part of the lookup answered a query to [classify_traced].
+#[cfg(feature = "debug")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Path {
+    /// The codepoint was ASCII and was looked up in the ASCII table.
+    Ascii,
+    /// The codepoint was above the range covered by the tables (`U+100000`
+    /// and up), which has no identifier characters.
+    AboveRange,
+    /// The codepoint was found in the perfect hash of single codepoint runs.
+    #[cfg(feature = "phf")]
+    Singleton,
+    /// The lookup descended into the trie.
+    Trie,
+}
+
+/// Behaves like [unicode_identifier_class], but also returns which [Path] the
+/// lookup took. This is useful for checking how often a real corpus takes the
+/// ASCII fast path.
+#[cfg(feature = "debug")]
+pub fn classify_traced(cp: char) -> (UnicodeIdentifierClass, Path) {
     if (cp as u32) < START_CODEPOINT {
-        return UnicodeIdentifierClass(ASCII_TABLE[cp as usize]);
+        return (UnicodeIdentifierClass(ascii_class(cp as u32)), Path::Ascii);
     }
 
     if (cp as u32) >= 0x100000 {
-        return UnicodeIdentifierClass(IDENTIFIER_OTHER);
+        return (UnicodeIdentifierClass(IDENTIFIER_OTHER), Path::AboveRange);
     }
 
     let cp = cp as u32;
-    let block = cp >> SHIFT;
-    debug_assert!(block < BLOCK_COUNT as u32);
-    let top = (block >> LOWER_BITS) as usize;
-    let bottom = (block & LOWER_MASK) as usize;
-    let level2_idx = LEVEL1_TABLE[top] as usize;
-    let leaf_idx = LEVEL2_TABLES[level2_idx * LOWER_SIZE + bottom] as usize;
-    let leaf = load_leaf(leaf_idx);
+    #[cfg(feature = "phf")]
+    if let Some(bits) = singleton_class(cp) {
+        return (UnicodeIdentifierClass(bits), Path::Singleton);
+    }
+
+    let leaf = STATIC_TABLE.block_leaf(cp);
+    let offset = (cp & BLOCK_MASK) as u16;
+    (STATIC_TABLE.leaf_value(leaf, offset), Path::Trie)
+}
+
+/// Looks `cp` up in the perfect hash of codepoints which make up a run by
+/// themselves, returning its class bits if it's one of them.
+#[cfg(feature = "phf")]
+#[inline]
+fn singleton_class(cp: u32) -> Option<u8> {
+    let bucket =
+        cp.wrapping_mul(PHF_BUCKET_MULTIPLIER) >> (32 - PHF_BUCKET_BITS);
+    let displacement = PHF_DISPLACEMENTS[bucket as usize] as u32;
+    let slot = (cp ^ displacement).wrapping_mul(PHF_SLOT_MULTIPLIER)
+        >> (32 - PHF_SLOT_BITS);
+    (PHF_KEYS[slot as usize] == cp).then(|| PHF_VALUES[slot as usize])
+}
+
+/// Returns the class bits of `cp` along with the first codepoint after `cp`
+/// which may have different class bits. Runs are cut at block boundaries, so
+/// the next run may have the same bits.
+fn run_at(cp: u32) -> (u8, u32) {
+    if cp < START_CODEPOINT {
+        return (ascii_class(cp), cp + 1);
+    }
+
+    if cp >= 0x100000 {
+        return (IDENTIFIER_OTHER, char::MAX as u32 + 1);
+    }
+
+    let (runs, values) = STATIC_TABLE.leaf_runs(STATIC_TABLE.block_leaf(cp));
+    let offset = (cp & BLOCK_MASK) as u16;
+    // the sentinel at the end of every leaf means idx is always in bounds.
+    let idx = runs.partition_point(|&start| start <= offset);
+    (values[idx - 1], (cp & !BLOCK_MASK) + runs[idx] as u32)
+}
+
+/// Returns the first codepoint of the run containing `cp`, the counterpart of
+/// the end returned by [run_at]. Like those ends, this may be in the middle of
+/// a maximal run, at a block boundary.
+fn run_start(cp: u32) -> u32 {
+    if cp < START_CODEPOINT {
+        return cp;
+    }
+
+    if cp >= 0x100000 {
+        return 0x100000;
+    }
+
+    let (runs, _) = STATIC_TABLE.leaf_runs(STATIC_TABLE.block_leaf(cp));
     let offset = (cp & BLOCK_MASK) as u16;
-    leaf_value(leaf, offset)
+    // runs[0] is always 0, so idx is at least 1.
+    let idx = runs.partition_point(|&start| start <= offset);
+    (cp & !BLOCK_MASK) + runs[idx - 1] as u32
+}
+
+/// Behaves like [unicode_identifier_class], but reads every entry of every
+/// table on each call and picks out the answer with masks, so the memory
+/// accesses and branches taken don't depend on `cp`.
+///
+/// This is orders of magnitude slower than [unicode_identifier_class],
+/// since it touches the whole trie (roughly 6 KiB) per codepoint. Only use it
+/// if your threat model includes an attacker timing lookups of secret text.
+/// The compiler isn't obliged to keep the selection branch free, so check the
+/// generated code for your target if it matters.
+#[cfg(feature = "constant-time")]
+pub fn unicode_identifier_class_ct(cp: char) -> UnicodeIdentifierClass {
+    let cp = cp as u32;
+    let mut ascii = 0;
+    for (i, &bits) in ASCII_TABLE.iter().enumerate() {
+        ascii = ct_select(ct_mask(i as u32 == cp), bits as u32, ascii);
+    }
+
+    // codepoints outside the trie still scan it; the result is discarded.
+    let block = cp >> SHIFT;
+    let top = block >> LOWER_BITS;
+    let bottom = block & LOWER_MASK;
+    let level2_idx = ct_level1_entry(top) * LOWER_SIZE as u32 + bottom;
+    let mut leaf = 0;
+    for (i, &entry) in LEVEL2_TABLES.iter().enumerate() {
+        leaf = ct_select(ct_mask(i as u32 == level2_idx), entry as u32, leaf);
+    }
+
+    let mut leaf_start = 0;
+    let mut leaf_end = 0;
+    for (i, bounds) in LEAF_OFFSETS.windows(2).enumerate() {
+        let mask = ct_mask(i as u32 == leaf);
+        leaf_start = ct_select(mask, bounds[0] as u32, leaf_start);
+        leaf_end = ct_select(mask, bounds[1] as u32, leaf_end);
+    }
+
+    // runs within a leaf are ascending, so the last one starting at or
+    // before offset is the one containing it.
+    let offset = cp & BLOCK_MASK;
+    let mut trie = 0;
+    for (i, (&start, &bits)) in
+        LEAF_RUN_STARTS.iter().zip(LEAF_RUN_VALUES).enumerate()
+    {
+        let i = i as u32;
+        let hit =
+            (i >= leaf_start) & (i < leaf_end) & (start as u32 <= offset);
+        trie = ct_select(ct_mask(hit), bits as u32, trie);
+    }
+
+    let bits = ct_select(
+        ct_mask(cp < START_CODEPOINT),
+        ascii,
+        ct_select(ct_mask(cp < 0x100000), trie, IDENTIFIER_OTHER as u32),
+    );
+    UnicodeIdentifierClass(bits as u8)
+}
+
+#[cfg(all(feature = "constant-time", not(compressed_level1)))]
+fn ct_level1_entry(top: u32) -> u32 {
+    let mut entry = 0;
+    for (i, &table) in LEVEL1_TABLE.iter().enumerate() {
+        entry = ct_select(ct_mask(i as u32 == top), table as u32, entry);
+    }
+    entry
+}
+
+#[cfg(all(feature = "constant-time", compressed_level1))]
+fn ct_level1_entry(top: u32) -> u32 {
+    let mut entry = 0;
+    for (&start, &table) in LEVEL1_RUN_STARTS.iter().zip(&LEVEL1_RUN_TABLES) {
+        entry = ct_select(ct_mask(start as u32 <= top), table as u32, entry);
+    }
+    entry
+}
+
+/// Returns all ones if `b` is true and zero otherwise.
+#[cfg(feature = "constant-time")]
+#[inline(always)]
+fn ct_mask(b: bool) -> u32 {
+    (b as u32).wrapping_neg()
+}
+
+/// Returns `a` where `mask` is set and `b` elsewhere.
+#[cfg(feature = "constant-time")]
+#[inline(always)]
+fn ct_select(mask: u32, a: u32, b: u32) -> u32 {
+    (a & mask) | (b & !mask)
+}
+
+/// Returns the class bits of the ASCII codepoint `cp`.
+#[cfg(not(feature = "ascii_match"))]
+#[inline(always)]
+fn ascii_class(cp: u32) -> u8 {
+    ASCII_TABLE[cp as usize]
+}
+
+/// Returns the class bits of the ASCII codepoint `cp` with a `match`
+/// generated from the Unicode data, which the compiler can turn into
+/// comparisons against immediates instead of a load from [ASCII_TABLE].
+#[cfg(feature = "ascii_match")]
+#[inline(always)]
+fn ascii_class(cp: u32) -> u8 {
+    ascii_class_match(cp)
 }
 
 const fn ascii_table() -> [u8; 128] {
@@ -117,6 +729,10 @@ pub fn is_identifier(cp: &[char]) -> bool {
         return false;
     }
 
+    if let Some(result) = ascii_is_identifier(cp) {
+        return result;
+    }
+
     if !unicode_identifier_class(cp[0]).is_start() {
         return false;
     }
@@ -134,6 +750,206 @@ pub fn is_identifier(cp: &[char]) -> bool {
     true
 }
 
+/// Checks a non-empty slice the same way as [is_identifier] if every
+/// character is ASCII, using only the ASCII table, and returns `None`
+/// otherwise. There are no joiners in ASCII, so every character after the
+/// first just has to be a continue character.
+#[inline]
+fn ascii_is_identifier(cp: &[char]) -> Option<bool> {
+    // a single pass without branches, which checks both that every character
+    // is ASCII and that it's a continue character.
+    let (ascii, continues) = cp[1..].iter().fold(
+        ((cp[0] as u32) < START_CODEPOINT, IDENTIFIER_CONTINUE),
+        |(ascii, bits), &c| {
+            let cp = c as u32;
+            (
+                ascii & (cp < START_CODEPOINT),
+                bits & ascii_class(cp & 0x7f),
+            )
+        },
+    );
+    ascii.then(|| {
+        ascii_class(cp[0] as u32) & IDENTIFIER_START != 0
+            && continues & IDENTIFIER_CONTINUE != 0
+    })
+}
+
+/// Options which relax or restrict the identifier syntax checked by
+/// [str_is_identifier_with]. The [Default] options check exactly the UAX #31
+/// default identifier syntax, the same as [str_is_identifier].
+///
+/// Options can be set field by field, or with the builder returned by
+/// [IdentifierOptions::new]:
+///
+/// ```
+/// use unicode_id_trie_rle::{IdentifierOptions, str_is_identifier_with};
+///
+/// let options = IdentifierOptions::new()
+///     .allow_leading_underscore(true)
+///     .max_bytes(8)
+///     .build();
+/// assert!(str_is_identifier_with("_private", options));
+/// assert!(!str_is_identifier_with("_too_long", options));
+/// assert_eq!(IdentifierOptions::new().build(), IdentifierOptions::default());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IdentifierOptions {
+    /// Accept `_` as the first character of an identifier, as Rust, C and
+    /// many other languages do.
+    ///
+    /// UAX #31 classifies `_` as `XID_Continue` but not `XID_Start`, so by
+    /// default `"_"` and `"_x"` are not identifiers. With this set, `_` is
+    /// treated as a start character in the first position only, so `"_"`,
+    /// `"_x"` and `"__"` are all accepted. Every other character is still
+    /// checked according to UAX #31.
+    pub allow_leading_underscore: bool,
+    /// Reject identifiers containing a character with the
+    /// `Default_Ignorable_Code_Point` property, other than ZWNJ and ZWJ.
+    ///
+    /// Some of these, such as the variation selectors and the Hangul fillers,
+    /// are identifier characters but usually render as nothing, so they can
+    /// make two identifiers which look the same compare unequal.
+    pub reject_default_ignorable: bool,
+    /// Accept a combining mark (general category `Mn`, `Mc` or `Me`) which is
+    /// a continue character as the first character of an identifier.
+    ///
+    /// This is a non-standard relaxation for parsers of writing systems
+    /// where words can begin with a mark, such as text which was split in the
+    /// middle of a grapheme cluster. UAX #31 never allows it.
+    #[cfg(feature = "general_category")]
+    pub allow_mark_start: bool,
+    /// Ignore any ZWNJ and ZWJ at the end of an identifier, accepting it if
+    /// it would be an identifier without them.
+    ///
+    /// UAX #31 only allows the joiners in the middle of an identifier unless
+    /// they are continue characters, so this is a non-standard relaxation for
+    /// callers which strip them instead of rejecting the identifier. With
+    /// this set, [identifier_len_with] leaves the trailing joiners out of the
+    /// length, which tells the caller how many bytes to trim.
+    pub trim_trailing_joiners: bool,
+    /// Ignore every ZWNJ and ZWJ when comparing identifiers with
+    /// [identifiers_equivalent], so `"a\u{200d}b"` and `"ab"` are the same
+    /// identifier.
+    ///
+    /// This is for symbol resolution in relaxed modes, where the joiners only
+    /// change how an identifier renders. It doesn't change which strings are
+    /// identifiers.
+    pub fold_joiners: bool,
+    /// Accept Private Use Area codepoints as identifier characters, as some
+    /// DSLs do to let users define their own symbols.
+    ///
+    /// UAX #31 never allows them. With this set, the BMP Private Use Area,
+    /// `U+E000..=U+F8FF`, is treated as start characters, and the
+    /// Supplementary Private Use Areas A and B, `U+F0000..=U+FFFFD` and
+    /// `U+100000..=U+10FFFD`, as continue characters. The noncharacters at
+    /// the end of planes 15 and 16 aren't private use, so they're still
+    /// rejected.
+    pub allow_private_use: bool,
+    /// Ignore every U+FEFF ZERO WIDTH NO-BREAK SPACE, which is also the byte
+    /// order mark, as if it weren't there, for tolerant lexers reading files
+    /// with stray byte order marks in them.
+    ///
+    /// UAX #31 never allows it, since it isn't a continue character. With
+    /// this set, [str_is_identifier_with] accepts a string if it would be an
+    /// identifier without them, even with
+    /// [IdentifierOptions::reject_default_ignorable], and [identifier_len_with]
+    /// counts them as part of the identifier they're in.
+    pub skip_bom: bool,
+    /// Reject identifiers longer than this many bytes of UTF-8.
+    ///
+    /// UAX #31 doesn't limit the length of identifiers, but languages often
+    /// do. The length is measured after any trailing joiners are trimmed by
+    /// [IdentifierOptions::trim_trailing_joiners].
+    pub max_bytes: Option<usize>,
+}
+
+impl IdentifierOptions {
+    /// Returns a builder for [IdentifierOptions], starting from the
+    /// [Default] options.
+    // the builder reads better chained off the type it builds.
+    #[allow(clippy::new_ret_no_self)]
+    pub const fn new() -> IdentifierOptionsBuilder {
+        IdentifierOptionsBuilder {
+            options: IdentifierOptions {
+                allow_leading_underscore: false,
+                reject_default_ignorable: false,
+                #[cfg(feature = "general_category")]
+                allow_mark_start: false,
+                trim_trailing_joiners: false,
+                fold_joiners: false,
+                allow_private_use: false,
+                skip_bom: false,
+                max_bytes: None,
+            },
+        }
+    }
+}
+
+/// Builds [IdentifierOptions] one option at a time, as returned by
+/// [IdentifierOptions::new]. Each method sets the field of the same name.
+#[derive(Clone, Copy, Debug)]
+#[must_use]
+pub struct IdentifierOptionsBuilder {
+    options: IdentifierOptions,
+}
+
+impl IdentifierOptionsBuilder {
+    /// See [IdentifierOptions::allow_leading_underscore].
+    pub const fn allow_leading_underscore(mut self, value: bool) -> Self {
+        self.options.allow_leading_underscore = value;
+        self
+    }
+
+    /// See [IdentifierOptions::reject_default_ignorable].
+    pub const fn reject_default_ignorable(mut self, value: bool) -> Self {
+        self.options.reject_default_ignorable = value;
+        self
+    }
+
+    /// See [IdentifierOptions::allow_mark_start].
+    #[cfg(feature = "general_category")]
+    pub const fn allow_mark_start(mut self, value: bool) -> Self {
+        self.options.allow_mark_start = value;
+        self
+    }
+
+    /// See [IdentifierOptions::trim_trailing_joiners].
+    pub const fn trim_trailing_joiners(mut self, value: bool) -> Self {
+        self.options.trim_trailing_joiners = value;
+        self
+    }
+
+    /// See [IdentifierOptions::fold_joiners].
+    pub const fn fold_joiners(mut self, value: bool) -> Self {
+        self.options.fold_joiners = value;
+        self
+    }
+
+    /// See [IdentifierOptions::allow_private_use].
+    pub const fn allow_private_use(mut self, value: bool) -> Self {
+        self.options.allow_private_use = value;
+        self
+    }
+
+    /// See [IdentifierOptions::skip_bom].
+    pub const fn skip_bom(mut self, value: bool) -> Self {
+        self.options.skip_bom = value;
+        self
+    }
+
+    /// Sets [IdentifierOptions::max_bytes] to `Some(value)`.
+    pub const fn max_bytes(mut self, value: usize) -> Self {
+        self.options.max_bytes = Some(value);
+        self
+    }
+
+    /// Returns the options which have been set.
+    pub const fn build(self) -> IdentifierOptions {
+        self.options
+    }
+}
+
 /// Checks if a given string is a unicode identifier, defined by Unicode
 /// Standard Annex #31.
 ///
@@ -142,18 +958,32 @@ pub fn is_identifier(cp: &[char]) -> bool {
 /// properties are used in determining whether something is a valid identifier.
 #[inline]
 pub fn str_is_identifier(s: &str) -> bool {
-    let mut iter = s.chars();
-    let Some(first) = iter.next() else {
-        return false;
-    };
+    str_is_identifier_with(s, IdentifierOptions::default())
+}
 
-    if !unicode_identifier_class(first).is_start() {
+/// Checks if a given string is a unicode identifier, defined by Unicode
+/// Standard Annex #31, with the deviations described by `options`.
+#[inline]
+pub fn str_is_identifier_with(s: &str, options: IdentifierOptions) -> bool {
+    let s = if options.trim_trailing_joiners {
+        s.trim_end_matches(['\u{200c}', '\u{200d}'])
+    } else {
+        s
+    };
+    if options.max_bytes.is_some_and(|max| s.len() > max) {
+        return false;
+    }
+    let mut iter = s.chars().filter(|&c| !is_skipped_with(c, options));
+    if !iter.next().is_some_and(|c| is_first_with(c, options)) {
         return false;
     }
 
     let mut iter = iter.peekable();
     while let Some(c) = iter.next() {
-        if !unicode_identifier_class(c).is_continue() {
+        if options.reject_default_ignorable && is_default_ignorable(c) {
+            return false;
+        }
+        if !is_continue_with(c, options) {
             // the two special characters are only allowed in the
             // middle, not the end.
             if (c != '\u{200c}' && c != '\u{200d}') || iter.peek().is_none() {
@@ -165,107 +995,2843 @@ pub fn str_is_identifier(s: &str) -> bool {
     true
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use proptest::prelude::*;
-    use std::{fs::File, path::PathBuf, sync::OnceLock};
+/// Returns whether `c` is ignored by [str_is_identifier_with] with `options`.
+#[inline]
+fn is_skipped_with(c: char, options: IdentifierOptions) -> bool {
+    options.skip_bom && c == '\u{feff}'
+}
 
-    const MAX_SCALAR: usize = 0x110000;
+/// Returns whether `c` can be the first character of an identifier checked by
+/// [str_is_identifier_with] with `options`.
+#[inline]
+fn is_first_with(c: char, options: IdentifierOptions) -> bool {
+    let underscore = options.allow_leading_underscore && c == '_';
+    #[cfg(feature = "general_category")]
+    let mark = options.allow_mark_start && is_mark_continue(c);
+    #[cfg(not(feature = "general_category"))]
+    let mark = false;
+    let private_use =
+        options.allow_private_use && ('\u{e000}'..='\u{f8ff}').contains(&c);
+    if !underscore
+        && !mark
+        && !private_use
+        && !unicode_identifier_class(c).is_start()
+    {
+        return false;
+    }
+    !(options.reject_default_ignorable && is_default_ignorable(c))
+}
 
-    fn derived_identifier_table() -> &'static [u8] {
-        static TABLE: OnceLock<Box<[u8]>> = OnceLock::new();
-        TABLE
-            .get_or_init(|| {
-                let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-                let derived_path =
-                    manifest_dir.join("./DerivedCoreProperties.txt");
-                let file = File::open(&derived_path).unwrap_or_else(|err| {
-                    panic!("failed to open {}: {err}", derived_path.display())
-                });
-
-                let parsed =
-                    unicode_id_trie_rle_derived_core_properties::parse(file)
-                        .unwrap_or_else(|err| {
-                            panic!("failed to parse derived data: {err}")
-                        });
-                let mut table = vec![0u8; MAX_SCALAR];
-                for (ch, props) in parsed {
-                    let mut bits = 0u8;
-                    for prop in props {
-                        if prop.contains("XID_Start") {
-                            bits |= IDENTIFIER_START;
-                        }
-                        if prop.contains("XID_Continue") {
-                            bits |= IDENTIFIER_CONTINUE;
-                        }
-                    }
-
-                    table[ch as usize] = bits;
-                }
+/// Returns whether `c` can be part of an identifier after the first character
+/// when checked by [str_is_identifier_with] with `options`.
+#[inline]
+fn is_continue_with(c: char, options: IdentifierOptions) -> bool {
+    unicode_identifier_class(c).is_continue()
+        || options.allow_private_use && is_private_use(c)
+}
+
+/// Returns whether `c` is in one of the Private Use Areas.
+#[inline]
+fn is_private_use(c: char) -> bool {
+    matches!(
+        c,
+        '\u{e000}'..='\u{f8ff}'
+            | '\u{f0000}'..='\u{ffffd}'
+            | '\u{100000}'..='\u{10fffd}'
+    )
+}
 
-                table.into_boxed_slice()
-            })
-            .as_ref()
+/// The tables for one of the older Unicode versions bundled by the
+/// `versioned` feature, laid out like the main tables.
+#[cfg(feature = "versioned")]
+struct VersionedTable {
+    version: (u8, u8, u8),
+    table: IdentifierTable<'static>,
+}
+
+/// Checks if `s` is an identifier, like [str_is_identifier], according to
+/// the identifier properties of the Unicode version `version`, given as
+/// `(major, minor, update)`. Returns `None` if the tables for `version`
+/// weren't bundled.
+///
+/// The tables this crate is built from are always available, and the
+/// overrides given by `UNICODE_OVERRIDES_PATH` only apply to them. The
+/// tables for every other version are built from the files in the directory
+/// named by `UNICODE_VERSIONS_DIR`; see the README.
+///
+/// ```rust
+/// use unicode_id_trie_rle::{str_is_identifier_version, unicode_version};
+///
+/// assert_eq!(str_is_identifier_version("héllo", unicode_version()), Some(true));
+/// assert_eq!(str_is_identifier_version("héllo", (1, 0, 0)), None);
+/// ```
+#[cfg(feature = "versioned")]
+pub fn str_is_identifier_version(
+    s: &str,
+    version: (u8, u8, u8),
+) -> Option<bool> {
+    if version == DATA_VERSION {
+        return Some(str_is_identifier(s));
     }
 
-    #[test]
-    fn unicode_identifier_class_matches_derived_core_properties() {
-        let table = derived_identifier_table();
-        for cp in 0..=0x10ffff {
-            let Some(ch) = char::from_u32(cp) else {
-                continue;
-            };
-            let expected = table[ch as usize];
-            if cp >= 0x100000 {
-                assert_eq!(
-                    expected, 0,
-                    "derived data marks unsupported codepoint U+{cp:06X}"
-                );
-            }
+    let table = VERSIONED_TABLES.iter().find(|x| x.version == version)?;
+    let mut iter = s.chars();
+    if !iter
+        .next()
+        .is_some_and(|c| table.table.classify(c).is_start())
+    {
+        return Some(false);
+    }
 
-            let class = unicode_identifier_class(ch);
-            assert_eq!(
-                class.is_start(),
+    let mut iter = iter.peekable();
+    while let Some(c) = iter.next() {
+        if !table.table.classify(c).is_continue() {
+            // the two special characters are only allowed in the
+            // middle, not the end.
+            if (c != '\u{200c}' && c != '\u{200d}') || iter.peek().is_none() {
+                return Some(false);
+            }
+        }
+    }
+
+    Some(true)
+}
+
+/// Returns the Unicode version of the tables used by every function other
+/// than [str_is_identifier_version], as `(major, minor, update)`.
+#[cfg(feature = "versioned")]
+pub const fn unicode_version() -> (u8, u8, u8) {
+    DATA_VERSION
+}
+
+/// Returns whether the tables used by every function other than
+/// [str_is_identifier_version] were built from the Unicode version
+/// `expected_version`, as `(major, minor, update)`.
+///
+/// This is a `const fn`, so a crate which depends on a specific Unicode
+/// version can fail to compile when linked against a build of this crate
+/// from any other:
+///
+/// ```
+/// const _: () = assert!(unicode_id_trie_rle::verify((17, 0, 0)));
+/// ```
+pub const fn verify(expected_version: (u8, u8, u8)) -> bool {
+    let (major, minor, update) = DATA_VERSION;
+    major == expected_version.0
+        && minor == expected_version.1
+        && update == expected_version.2
+}
+
+/// Returns the Unicode versions [str_is_identifier_version] accepts, in
+/// ascending order.
+#[cfg(feature = "versioned")]
+pub fn bundled_unicode_versions() -> impl Iterator<Item = (u8, u8, u8)> {
+    let mut versions = [DATA_VERSION; VERSIONED_TABLES.len() + 1];
+    for (version, table) in versions.iter_mut().zip(&VERSIONED_TABLES) {
+        *version = table.version;
+    }
+    versions.sort_unstable();
+    versions.into_iter()
+}
+
+/// Returns whether `c` is an identifier character with the
+/// `Default_Ignorable_Code_Point` property, other than ZWNJ and ZWJ.
+#[inline]
+fn is_default_ignorable(c: char) -> bool {
+    ranges_contain(&DEFAULT_IGNORABLE_FIRSTS, &DEFAULT_IGNORABLE_LASTS, c)
+}
+
+/// Returns whether `c` is in one of the sorted, disjoint inclusive ranges
+/// given by their first and last codepoints.
+#[inline]
+fn ranges_contain(firsts: &[u32], lasts: &[u32], c: char) -> bool {
+    let cp = c as u32;
+    let idx = firsts.partition_point(|&first| first <= cp);
+    idx != 0 && lasts[idx - 1] >= cp
+}
+
+/// Returns whether `c` can come next in an identifier after `prev`, or begin
+/// one if `prev` is `None`, for scanners which see one character at a time.
+///
+/// Without a `prev`, `c` must be a start character, and otherwise a continue
+/// character. ZWNJ and ZWJ are only valid between characters, so they're
+/// only accepted after a continue character, even where the data makes them
+/// continue characters themselves.
+///
+/// This can't see what follows `c`, so the end of the string is the caller's
+/// responsibility: [str_is_identifier] rejects a trailing joiner unless it's
+/// a continue character, so a scanner matching it should check
+/// [UnicodeIdentifierClass::is_continue] for a joiner at the end.
+///
+/// ```
+/// use unicode_id_trie_rle::is_valid_after;
+///
+/// assert!(is_valid_after('a', None));
+/// assert!(!is_valid_after('1', None));
+/// assert!(is_valid_after('1', Some('a')));
+/// assert!(is_valid_after('\u{200d}', Some('a')));
+/// assert!(!is_valid_after('\u{200d}', Some('-')));
+/// ```
+#[inline]
+pub fn is_valid_after(c: char, prev: Option<char>) -> bool {
+    let Some(prev) = prev else {
+        return unicode_identifier_class(c).is_start();
+    };
+
+    if c == '\u{200c}' || c == '\u{200d}' {
+        return unicode_identifier_class(prev).is_continue();
+    }
+    unicode_identifier_class(c).is_continue()
+}
+
+/// Checks if `s` could be the part of an identifier following its first
+/// character, such as the pieces left after splitting `foo::bar` on `::`.
+///
+/// Unlike [str_is_identifier], the first `char` doesn't have to be a start
+/// character, so `"123"` is a fragment but not an identifier. Otherwise the
+/// rules are the same: `s` must not be empty, and every `char` must be a
+/// continue character, except that a zero width joiner or non-joiner which
+/// isn't one is allowed anywhere but at the end. Every identifier is a
+/// fragment.
+#[inline]
+pub fn is_identifier_fragment(s: &str) -> bool {
+    if s.is_empty() {
+        return false;
+    }
+
+    let mut iter = s.chars().peekable();
+    while let Some(c) = iter.next() {
+        if !unicode_identifier_class(c).is_continue() {
+            // the two special characters are only allowed in the
+            // middle, not the end.
+            if (c != '\u{200c}' && c != '\u{200d}') || iter.peek().is_none() {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+/// Returns whether `a` and `b` are both identifiers according to
+/// [str_is_identifier] and are equal ignoring ASCII case, for languages with
+/// case insensitive identifiers.
+///
+/// Only ASCII letters are folded, so `Foo` and `foo` are equal but `Ärger` and
+/// `ärger` aren't. Full Unicode case folding needs tables this crate doesn't
+/// have.
+#[inline]
+pub fn identifiers_eq_ignore_ascii_case(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b) && str_is_identifier(a) && str_is_identifier(b)
+}
+
+/// Returns whether `a` and `b` are both identifiers according to
+/// [str_is_identifier_with] and name the same identifier under `options`.
+///
+/// With [IdentifierOptions::trim_trailing_joiners], joiners at the end of
+/// either identifier are ignored, and with [IdentifierOptions::fold_joiners]
+/// every joiner is. Likewise, [IdentifierOptions::skip_bom] ignores every
+/// U+FEFF. Under the default options this is just string equality of
+/// valid identifiers.
+///
+/// ```
+/// use unicode_id_trie_rle::{IdentifierOptions, identifiers_equivalent};
+///
+/// let options = IdentifierOptions::new().fold_joiners(true).build();
+/// assert!(identifiers_equivalent("a\u{200d}b", "ab", options));
+/// assert!(!identifiers_equivalent("a\u{200d}b", "ab", Default::default()));
+/// ```
+pub fn identifiers_equivalent(
+    a: &str,
+    b: &str,
+    options: IdentifierOptions,
+) -> bool {
+    fn normalize(
+        s: &str,
+        options: IdentifierOptions,
+    ) -> impl Iterator<Item = char> + '_ {
+        let s = if options.trim_trailing_joiners {
+            s.trim_end_matches(['\u{200c}', '\u{200d}'])
+        } else {
+            s
+        };
+        s.chars().filter(move |&c| {
+            let joiner = c == '\u{200c}' || c == '\u{200d}';
+            !(is_skipped_with(c, options) || options.fold_joiners && joiner)
+        })
+    }
+
+    str_is_identifier_with(a, options)
+        && str_is_identifier_with(b, options)
+        && normalize(a, options).eq(normalize(b, options))
+}
+
+/// Turns `s` into an identifier for generated code, such as a struct field
+/// named after a JSON key, by replacing every character which isn't a
+/// continue character with `replacement`. If the result doesn't start with a
+/// start character, `_` is put in front of it, so `"123abc"` becomes
+/// `"_123abc"` and `""` becomes `"_"`.
+///
+/// `_` isn't a start character in UAX #31, so the result is only guaranteed
+/// to be an identifier with [IdentifierOptions::allow_leading_underscore],
+/// which most target languages allow. It's an identifier according to
+/// [str_is_identifier] whenever `s` starts with a start character.
+///
+/// ```
+/// use unicode_id_trie_rle::sanitize_identifier;
+///
+/// assert_eq!(sanitize_identifier("content-type", '_'), "content_type");
+/// assert_eq!(sanitize_identifier("2fa", '_'), "_2fa");
+/// ```
+///
+/// # Panics
+///
+/// Panics if `replacement` isn't a continue character.
+#[cfg(feature = "alloc")]
+pub fn sanitize_identifier(s: &str, replacement: char) -> String {
+    assert!(
+        unicode_identifier_class(replacement).is_continue(),
+        "the replacement {replacement:?} isn't a continue character"
+    );
+
+    let replace = |c: char| {
+        if unicode_identifier_class(c).is_continue() {
+            c
+        } else {
+            replacement
+        }
+    };
+    let mut result = String::with_capacity(s.len() + 1);
+    let first = s.chars().next().map(replace);
+    if !first.is_some_and(|c| unicode_identifier_class(c).is_start()) {
+        result.push('_');
+    }
+    result.extend(s.chars().map(replace));
+    result
+}
+
+/// Returns `s` if it's an identifier with
+/// [IdentifierOptions::allow_leading_underscore], which is what
+/// [sanitize_identifier] guarantees, and otherwise [sanitize_identifier] of it
+/// with `_` replacing the invalid characters. Only the second case allocates,
+/// which matters when generating code for many names which are mostly
+/// identifiers already, and normalizing the result again borrows it.
+///
+/// ```
+/// use std::borrow::Cow;
+/// use unicode_id_trie_rle::normalize_identifier;
+///
+/// assert!(matches!(normalize_identifier("foo"), Cow::Borrowed("foo")));
+/// assert!(matches!(normalize_identifier("_1"), Cow::Borrowed("_1")));
+/// assert_eq!(normalize_identifier("foo-bar"), "foo_bar");
+/// ```
+#[cfg(feature = "alloc")]
+pub fn normalize_identifier(s: &str) -> Cow<'_, str> {
+    let options = IdentifierOptions::new()
+        .allow_leading_underscore(true)
+        .build();
+    if str_is_identifier_with(s, options) {
+        Cow::Borrowed(s)
+    } else {
+        Cow::Owned(sanitize_identifier(s, '_'))
+    }
+}
+
+/// Returns the entry of `symbols` equal to `name`, if `name` is an identifier
+/// according to [str_is_identifier] and is in `symbols`. Names which aren't
+/// identifiers are rejected without searching `symbols`.
+///
+/// `symbols` is searched linearly, so it can be in any order. Use
+/// [resolve_sorted] for large tables which are sorted.
+///
+/// ```
+/// use unicode_id_trie_rle::resolve;
+///
+/// let symbols = ["foo", "bar", "\u{e9}t\u{e9}"];
+/// assert_eq!(resolve("foo", &symbols), Some("foo"));
+/// assert_eq!(resolve("baz", &symbols), None);
+/// ```
+pub fn resolve<'a>(name: &str, symbols: &'a [&'a str]) -> Option<&'a str> {
+    if !str_is_identifier(name) {
+        return None;
+    }
+
+    symbols.iter().find(|&&x| x == name).copied()
+}
+
+/// Like [resolve], but binary searches `symbols`, so it must be sorted, as by
+/// [slice::sort_unstable]. Otherwise the result is unspecified, the same as
+/// for [slice::binary_search].
+///
+/// ```
+/// use unicode_id_trie_rle::resolve_sorted;
+///
+/// let symbols = ["bar", "foo", "\u{e9}t\u{e9}"];
+/// assert_eq!(resolve_sorted("foo", &symbols), Some("foo"));
+/// assert_eq!(resolve_sorted("baz", &symbols), None);
+/// ```
+pub fn resolve_sorted<'a>(
+    name: &str,
+    symbols: &'a [&'a str],
+) -> Option<&'a str> {
+    if !str_is_identifier(name) {
+        return None;
+    }
+
+    let idx = symbols.binary_search(&name).ok()?;
+    Some(symbols[idx])
+}
+
+/// Returns the length in bytes of the longest prefix of `s` which is an
+/// identifier according to [str_is_identifier], or 0 if `s` doesn't start
+/// with one. This is what a lexer needs to split an identifier token off the
+/// front of its input.
+///
+/// ```
+/// use unicode_id_trie_rle::identifier_len;
+///
+/// assert_eq!(identifier_len("café + 1"), "café".len());
+/// assert_eq!(identifier_len("1abc"), 0);
+/// ```
+#[inline]
+pub fn identifier_len(s: &str) -> usize {
+    identifier_len_with(s, IdentifierOptions::default())
+}
+
+/// Returns the length in bytes of the longest prefix of `s` which is an
+/// identifier according to [str_is_identifier_with] with `options`, or 0 if
+/// `s` doesn't start with one.
+///
+/// With [IdentifierOptions::trim_trailing_joiners], any joiners at the end of
+/// the identifier are left out of the length, even though
+/// [str_is_identifier_with] accepts the string with them. For an identifier
+/// followed by joiners, the difference from `s.len()` is the number of bytes
+/// to trim.
+///
+/// ```
+/// use unicode_id_trie_rle::{IdentifierOptions, identifier_len_with};
+///
+/// let mut options = IdentifierOptions::default();
+/// options.trim_trailing_joiners = true;
+/// assert_eq!(identifier_len_with("ab\u{200d}", options), 2);
+/// assert_eq!(identifier_len_with("a\u{200d}b", options), 5);
+/// ```
+#[inline]
+pub fn identifier_len_with(s: &str, options: IdentifierOptions) -> usize {
+    // whether a prefix is an identifier only depends on the prefix, so the
+    // longest one within the limit is the longest one of the truncated `s`.
+    let s = match options.max_bytes {
+        Some(mut max) if max < s.len() => {
+            while !s.is_char_boundary(max) {
+                max -= 1;
+            }
+            &s[..max]
+        }
+        _ => s,
+    };
+    let mut iter = s
+        .char_indices()
+        .skip_while(|&(_, c)| is_skipped_with(c, options));
+    match iter.next() {
+        Some((_, c)) if is_first_with(c, options) => {}
+        _ => return 0,
+    }
+
+    // `len` is `s.len()` while everything so far is part of the identifier,
+    // or the offset of the joiners at the end otherwise, since an identifier
+    // can't end in one.
+    let mut len = s.len();
+    for (i, c) in iter {
+        let joiner = c == '\u{200c}' || c == '\u{200d}';
+        if is_skipped_with(c, options) {
+            continue;
+        }
+        if options.reject_default_ignorable && is_default_ignorable(c) {
+            return len.min(i);
+        }
+        if is_continue_with(c, options)
+            && !(joiner && options.trim_trailing_joiners)
+        {
+            len = s.len();
+        } else if joiner {
+            if len == s.len() {
+                len = i;
+            }
+        } else {
+            return len.min(i);
+        }
+    }
+
+    len
+}
+
+/// Returns an iterator over the byte ranges of the maximal identifiers in `s`,
+/// in order. Each identifier starts at the first start character after the
+/// end of the previous one, so `1abc` contains the identifier `abc`.
+///
+/// ```
+/// use unicode_id_trie_rle::identifier_spans;
+///
+/// let s = "x1 = foo(2bar)";
+/// let spans = identifier_spans(s).map(|x| &s[x]).collect::<Vec<_>>();
+/// assert_eq!(spans, ["x1", "foo", "bar"]);
+/// ```
+pub fn identifier_spans(s: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut offset = 0;
+    core::iter::from_fn(move || {
+        while offset < s.len() {
+            let rest = &s[offset..];
+            let len = identifier_len(rest);
+            if len > 0 {
+                let span = offset..offset + len;
+                offset += len;
+                return Some(span);
+            }
+
+            offset += rest.chars().next().map_or(1, char::len_utf8);
+        }
+
+        None
+    })
+}
+
+/// Returns the byte range of the first maximal identifier in `s`, or `None`
+/// if `s` doesn't contain one. This is the first span [identifier_spans]
+/// would return.
+///
+/// ```
+/// use unicode_id_trie_rle::find_identifier;
+///
+/// assert_eq!(find_identifier("(1 + foo)"), Some(5..8));
+/// assert_eq!(find_identifier("1 + 2"), None);
+/// ```
+#[inline]
+pub fn find_identifier(s: &str) -> Option<Range<usize>> {
+    identifier_spans(s).next()
+}
+
+/// Returns the byte offset of the first start character in `s` at or after
+/// byte `from`, or `None` if there isn't one. If `from` is inside a `char`,
+/// the search begins at the next `char`, so editors can pass a cursor position
+/// straight through to implement "jump to next identifier".
+///
+/// ```
+/// use unicode_id_trie_rle::next_identifier_start;
+///
+/// let s = "x1 = 2café";
+/// assert_eq!(next_identifier_start(s, 0), Some(0));
+/// assert_eq!(next_identifier_start(s, 1), Some(6));
+/// assert_eq!(next_identifier_start(s, 7), Some(7));
+/// assert_eq!(next_identifier_start(s, 10), None);
+/// ```
+pub fn next_identifier_start(s: &str, from: usize) -> Option<usize> {
+    let mut from = from.min(s.len());
+    while !s.is_char_boundary(from) {
+        from += 1;
+    }
+
+    s[from..]
+        .char_indices()
+        .find(|&(_, c)| unicode_identifier_class(c).is_start())
+        .map(|(i, _)| from + i)
+}
+
+/// Checks if a given string is a unicode identifier, the same as
+/// [str_is_identifier], which also doesn't contain any `char` from the
+/// `disallowed` ranges. This is useful for forbidding confusable or invisible
+/// codepoints beyond what UAX #31 forbids, such as variation selectors.
+///
+/// If `disallowed` is sorted and its ranges don't overlap, each `char` is
+/// binary searched for; otherwise every range is checked.
+///
+/// ```
+/// use unicode_id_trie_rle::str_is_identifier_excluding;
+///
+/// let cyrillic = ['\u{400}'..='\u{4ff}'];
+/// assert!(str_is_identifier_excluding("apple", &cyrillic));
+/// assert!(!str_is_identifier_excluding("\u{430}pple", &cyrillic));
+/// ```
+pub fn str_is_identifier_excluding(
+    s: &str,
+    disallowed: &[RangeInclusive<char>],
+) -> bool {
+    if !str_is_identifier(s) {
+        return false;
+    }
+
+    let sorted = disallowed.is_sorted_by(|a, b| a.end() < b.start());
+    let is_disallowed = |c: char| {
+        if sorted {
+            let idx = disallowed.partition_point(|x| *x.start() <= c);
+            idx > 0 && disallowed[idx - 1].contains(&c)
+        } else {
+            disallowed.iter().any(|x| x.contains(&c))
+        }
+    };
+    !s.chars().any(is_disallowed)
+}
+
+/// Checks if a given string is an identifier according to the Rust 2021
+/// edition's grammar, which is
+///
+/// ```text
+/// XID_Start XID_Continue* | _ XID_Continue+
+/// ```
+///
+/// This differs from [str_is_identifier] in accepting a leading `_` as long as
+/// it isn't the whole identifier. Keywords aren't rejected, since they're
+/// identifiers lexically.
+#[inline]
+pub fn is_rust_2021_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    let is_continue = |c| unicode_identifier_class(c).is_continue();
+    match chars.next() {
+        Some('_') => !chars.as_str().is_empty() && chars.all(is_continue),
+        Some(c) if unicode_identifier_class(c).is_start() => {
+            chars.all(is_continue)
+        }
+        _ => false,
+    }
+}
+
+/// Every Rust keyword, strict or reserved, up to the 2024 edition which can be
+/// written as a raw identifier, sorted so it can be binary searched. `self`,
+/// `Self`, `super` and `crate` can't be raw identifiers, so they're left out.
+const RAW_ESCAPABLE_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const",
+    "continue", "do", "dyn", "else", "enum", "extern", "false", "final", "fn",
+    "for", "gen", "if", "impl", "in", "let", "loop", "macro", "match", "mod",
+    "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized",
+    "use", "virtual", "where", "while", "yield",
+];
+
+/// Returns whether `s` must be written as the raw identifier `r#s` to be used
+/// as an identifier in Rust, meaning it's a valid identifier which is also a
+/// keyword.
+///
+/// This is false for identifiers which aren't keywords, for anything which
+/// isn't an identifier to begin with, and for `self`, `Self`, `super` and
+/// `crate`, since those can't be raw identifiers at all. Weak keywords such as
+/// `union` are usable as plain identifiers, so they don't need escaping.
+#[inline]
+pub fn needs_raw_escape(s: &str) -> bool {
+    is_rust_2021_identifier(s)
+        && RAW_ESCAPABLE_KEYWORDS.binary_search(&s).is_ok()
+}
+
+/// Checks if the UTF-8 encoded `bytes` are a unicode identifier, defined by
+/// Unicode Standard Annex #31, the same as [str_is_identifier].
+///
+/// The UTF-8 is validated as it is classified rather than in a separate pass,
+/// and invalid UTF-8 is never an identifier.
+#[inline]
+pub fn bytes_is_identifier(bytes: &[u8]) -> bool {
+    let Some((first, mut i)) = decode_utf8(bytes) else {
+        return false;
+    };
+
+    if !unicode_identifier_class(first).is_start() {
+        return false;
+    }
+
+    while i < bytes.len() {
+        let Some((c, len)) = decode_utf8(&bytes[i..]) else {
+            return false;
+        };
+        i += len;
+        if !unicode_identifier_class(c).is_continue() {
+            // the two special characters are only allowed in the
+            // middle, not the end.
+            if (c != '\u{200c}' && c != '\u{200d}') || i == bytes.len() {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+/// Checks if the UTF-32 codepoints `cps` are a unicode identifier, defined by
+/// Unicode Standard Annex #31, the same as [is_identifier].
+///
+/// Each value is validated as it is classified rather than in a separate
+/// pass, and a surrogate or a value above `U+10FFFF` is never part of an
+/// identifier.
+#[inline]
+pub fn u32s_is_identifier(cps: &[u32]) -> bool {
+    let Some((&first, rest)) = cps.split_first() else {
+        return false;
+    };
+
+    if !char::from_u32(first)
+        .is_some_and(|c| unicode_identifier_class(c).is_start())
+    {
+        return false;
+    }
+
+    for (i, &cp) in rest.iter().enumerate() {
+        let Some(c) = char::from_u32(cp) else {
+            return false;
+        };
+        if !unicode_identifier_class(c).is_continue() {
+            // the two special characters are only allowed in the
+            // middle, not the end.
+            if (c != '\u{200c}' && c != '\u{200d}') || i + 1 == rest.len() {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+/// Decodes the first `char` in `bytes` along with its length in bytes, or
+/// returns `None` if `bytes` doesn't start with a valid UTF-8 sequence.
+#[inline]
+fn decode_utf8(bytes: &[u8]) -> Option<(char, usize)> {
+    let b0 = *bytes.first()?;
+    if b0 < 0x80 {
+        return Some((b0 as char, 1));
+    }
+
+    let (len, min, init) = match b0 {
+        0xc2..=0xdf => (2, 0x80, (b0 & 0x1f) as u32),
+        0xe0..=0xef => (3, 0x800, (b0 & 0x0f) as u32),
+        0xf0..=0xf4 => (4, 0x10000, (b0 & 0x07) as u32),
+        _ => return None,
+    };
+    let mut cp = init;
+    for &b in bytes.get(1..len)? {
+        if b & 0xc0 != 0x80 {
+            return None;
+        }
+        cp = (cp << 6) | (b & 0x3f) as u32;
+    }
+
+    // reject overlong encodings, then let char::from_u32 reject surrogates
+    // and values past U+10FFFF.
+    if cp < min {
+        return None;
+    }
+    char::from_u32(cp).map(|c| (c, len))
+}
+
+/// Checks if a given string is a unicode identifier, the same as
+/// [str_is_identifier], but in a `const` context. On failure, returns the
+/// byte offset of the first character which can't appear where it does, or 0
+/// if `s` is empty.
+///
+/// This uses a slower linear search of each leaf, so prefer
+/// [str_is_identifier] outside of `const` contexts.
+///
+/// ```
+/// use unicode_id_trie_rle::validate_identifier;
+///
+/// const VALID: Result<(), usize> = validate_identifier("r\u{e9}sum\u{e9}");
+/// assert_eq!(VALID, Ok(()));
+/// assert_eq!(validate_identifier("foo-bar"), Err(3));
+/// assert_eq!(validate_identifier("1st"), Err(0));
+/// ```
+pub const fn validate_identifier(s: &str) -> Result<(), usize> {
+    let bytes = s.as_bytes();
+    if bytes.is_empty() {
+        return Err(0);
+    }
+
+    let (first, mut i) = decode_str_char(bytes, 0);
+    if class_bits_const(first) & IDENTIFIER_START == 0 {
+        return Err(0);
+    }
+
+    while i < bytes.len() {
+        let (cp, len) = decode_str_char(bytes, i);
+        if class_bits_const(cp) & IDENTIFIER_CONTINUE == 0 {
+            // the two special characters are only allowed in the middle,
+            // not the end.
+            let joiner = cp == 0x200c || cp == 0x200d;
+            if !joiner || i + len == bytes.len() {
+                return Err(i);
+            }
+        }
+        i += len;
+    }
+
+    Ok(())
+}
+
+/// The kind of error in an [identifier_error_code] for an empty string.
+pub const ERROR_EMPTY: u32 = 1;
+/// The kind of error in an [identifier_error_code] for a first character
+/// which can't start an identifier.
+pub const ERROR_INVALID_START: u32 = 2;
+/// The kind of error in an [identifier_error_code] for a later character
+/// which can't continue an identifier.
+pub const ERROR_INVALID_CONTINUE: u32 = 3;
+/// The kind of error in an [identifier_error_code] for a ZWNJ or ZWJ at the
+/// end of the string, where UAX #31 doesn't allow them.
+pub const ERROR_TRAILING_JOINER: u32 = 4;
+
+/// How far the error kind is shifted in an [identifier_error_code].
+pub const ERROR_KIND_SHIFT: u32 = 28;
+/// The bits of an [identifier_error_code] which hold the byte offset. Offsets
+/// which don't fit are capped to this value.
+pub const ERROR_OFFSET_MASK: u32 = (1 << ERROR_KIND_SHIFT) - 1;
+
+/// Checks `s` like [str_is_identifier], packing the reason it isn't an
+/// identifier into a single `u32`, or returning 0 if it is one.
+///
+/// The top 4 bits hold the kind of error, one of [ERROR_EMPTY],
+/// [ERROR_INVALID_START], [ERROR_INVALID_CONTINUE] or
+/// [ERROR_TRAILING_JOINER], and the low 28 bits ([ERROR_OFFSET_MASK]) hold the
+/// byte offset of the offending character, capped to [ERROR_OFFSET_MASK] for
+/// strings longer than 256 MiB:
+///
+/// ```text
+///  31     28 27                                                  0
+/// +---------+-----------------------------------------------------+
+/// |  kind   |                     byte offset                     |
+/// +---------+-----------------------------------------------------+
+/// ```
+///
+/// ```rust
+/// use unicode_id_trie_rle::{
+///     ERROR_INVALID_CONTINUE, ERROR_KIND_SHIFT, ERROR_OFFSET_MASK,
+///     identifier_error_code,
+/// };
+///
+/// assert_eq!(identifier_error_code("foo"), 0);
+/// let code = identifier_error_code("foo-bar");
+/// assert_eq!(code >> ERROR_KIND_SHIFT, ERROR_INVALID_CONTINUE);
+/// assert_eq!(code & ERROR_OFFSET_MASK, 3);
+/// ```
+pub fn identifier_error_code(s: &str) -> u32 {
+    let error = |kind: u32, offset: usize| {
+        let offset = offset.min(ERROR_OFFSET_MASK as usize) as u32;
+        kind << ERROR_KIND_SHIFT | offset
+    };
+
+    let mut iter = s.char_indices();
+    let Some((_, first)) = iter.next() else {
+        return error(ERROR_EMPTY, 0);
+    };
+    if !unicode_identifier_class(first).is_start() {
+        return error(ERROR_INVALID_START, 0);
+    }
+
+    for (i, c) in iter {
+        if unicode_identifier_class(c).is_continue() {
+            continue;
+        }
+        if c != '\u{200c}' && c != '\u{200d}' {
+            return error(ERROR_INVALID_CONTINUE, i);
+        }
+        if i + c.len_utf8() == s.len() {
+            return error(ERROR_TRAILING_JOINER, i);
+        }
+    }
+
+    0
+}
+
+/// Decodes the `char` starting at byte `i` of the valid UTF-8 in `bytes`,
+/// returning it along with its length in bytes.
+const fn decode_str_char(bytes: &[u8], i: usize) -> (u32, usize) {
+    let b0 = bytes[i];
+    let (len, mut cp) = match b0 {
+        0x00..=0x7f => return (b0 as u32, 1),
+        0xc0..=0xdf => (2, (b0 & 0x1f) as u32),
+        0xe0..=0xef => (3, (b0 & 0x0f) as u32),
+        _ => (4, (b0 & 0x07) as u32),
+    };
+    let mut j = 1;
+    while j < len {
+        cp = (cp << 6) | (bytes[i + j] & 0x3f) as u32;
+        j += 1;
+    }
+    (cp, len)
+}
+
+/// Returns the class bits of `cp` the same as [unicode_identifier_class], but
+/// can be evaluated at compile time.
+const fn class_bits_const(cp: u32) -> u8 {
+    if cp < START_CODEPOINT {
+        return ASCII_TABLE[cp as usize];
+    }
+
+    if cp >= 0x100000 {
+        return IDENTIFIER_OTHER;
+    }
+
+    let block = cp >> SHIFT;
+    let top = (block >> LOWER_BITS) as usize;
+    let bottom = (block & LOWER_MASK) as usize;
+    #[cfg(not(compressed_level1))]
+    let level2_idx = LEVEL1_TABLE[top] as usize;
+    #[cfg(compressed_level1)]
+    let level2_idx = {
+        let mut idx = 0;
+        while idx + 1 < LEVEL1_RUN_STARTS.len()
+            && LEVEL1_RUN_STARTS[idx + 1] as usize <= top
+        {
+            idx += 1;
+        }
+        LEVEL1_RUN_TABLES[idx] as usize
+    };
+    let leaf = LEVEL2_TABLES[level2_idx * LOWER_SIZE + bottom] as usize;
+
+    let offset = (cp & BLOCK_MASK) as u16;
+    let mut i = LEAF_OFFSETS[leaf] as usize;
+    let end = LEAF_OFFSETS[leaf + 1] as usize;
+    let mut bits = IDENTIFIER_OTHER;
+    while i < end && LEAF_RUN_STARTS[i] <= offset {
+        bits = LEAF_RUN_VALUES[i];
+        i += 1;
+    }
+    bits
+}
+
+/// Expands to the string literal `$s` after checking at compile time that it
+/// is a unicode identifier, as defined by [str_is_identifier]. Otherwise, the
+/// build fails with the byte offset of the offending character.
+///
+/// ```
+/// use unicode_id_trie_rle::ident;
+///
+/// const NAME: &str = ident!("r\u{e9}sum\u{e9}");
+/// assert_eq!(NAME, "r\u{e9}sum\u{e9}");
+/// ```
+#[cfg(feature = "macros")]
+#[macro_export]
+macro_rules! ident {
+    ($s:literal) => {{
+        const IDENT: &str = $s;
+        const _: () = $crate::__assert_identifier(IDENT);
+        IDENT
+    }};
+}
+
+/// Panics with the position of the offending character if `s` isn't an
+/// identifier. Only meant to be called by [ident].
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub const fn __assert_identifier(s: &str) {
+    let Err(mut offset) = validate_identifier(s) else {
+        return;
+    };
+
+    const PREFIX: &[u8] = b"not an identifier: invalid character at byte ";
+    let mut message = [0u8; PREFIX.len() + 20];
+    let mut len = 0;
+    while len < PREFIX.len() {
+        message[len] = PREFIX[len];
+        len += 1;
+    }
+    let digits_start = len;
+    loop {
+        message[len] = b'0' + (offset % 10) as u8;
+        len += 1;
+        offset /= 10;
+        if offset == 0 {
+            break;
+        }
+    }
+    // the digits were written least significant first.
+    let (mut lo, mut hi) = (digits_start, len - 1);
+    while lo < hi {
+        let digit = message[lo];
+        message[lo] = message[hi];
+        message[hi] = digit;
+        lo += 1;
+        hi -= 1;
+    }
+
+    match core::str::from_utf8(message.split_at(len).0) {
+        Ok(message) => panic!("{}", message),
+        Err(_) => panic!("not an identifier"),
+    }
+}
+
+/// Returns whether `c` is a start character which is also a letter, meaning
+/// its general category is one of `Lu`, `Ll`, `Lt`, `Lm` or `Lo`.
+///
+/// This differs from [UnicodeIdentifierClass::is_start] only for the start
+/// characters which aren't letters: the letter numbers (`Nl`), such as the
+/// Roman numerals `U+2160..U+2188` and the Hangzhou numerals, along with the
+/// handful of `Other_ID_Start` codepoints kept for backwards compatibility,
+/// `U+1885`, `U+1886`, `U+2118` and `U+212E`.
+#[cfg(feature = "general_category")]
+#[inline]
+pub fn is_letter_start(c: char) -> bool {
+    if !unicode_identifier_class(c).is_start() {
+        return false;
+    }
+
+    let cp = c as u32;
+    let idx = NON_LETTER_START_FIRSTS.partition_point(|&first| first <= cp);
+    idx == 0 || NON_LETTER_START_LASTS[idx - 1] < cp
+}
+
+/// Returns whether `c` is a continue but not start character which is a
+/// combining mark, meaning its general category is `Mn`, `Mc` or `Me`.
+#[cfg(feature = "general_category")]
+#[inline]
+fn is_mark_continue(c: char) -> bool {
+    ranges_contain(&MARK_CONTINUE_FIRSTS, &MARK_CONTINUE_LASTS, c)
+}
+
+/// Returns whether `c` has the `Other_ID_Start` property, one of the
+/// codepoints kept as `ID_Start` for backwards compatibility even though its
+/// general category wouldn't make it one. This is only meant for explaining
+/// why a codepoint is an identifier character.
+///
+/// This is built from the `PropList.txt` bundled with this crate, or the one
+/// named by `UNICODE_PROPLIST_PATH` at build time if it's set.
+#[cfg(feature = "other_id")]
+pub fn is_other_id_start(c: char) -> bool {
+    ranges_contain(&OTHER_ID_START_FIRSTS, &OTHER_ID_START_LASTS, c)
+}
+
+/// Returns whether `c` has the `Other_ID_Continue` property, the counterpart
+/// of [is_other_id_start] for `ID_Continue`.
+#[cfg(feature = "other_id")]
+pub fn is_other_id_continue(c: char) -> bool {
+    ranges_contain(&OTHER_ID_CONTINUE_FIRSTS, &OTHER_ID_CONTINUE_LASTS, c)
+}
+
+/// Returns a short, stable label for `c`, meant for logs and reports:
+///
+/// - `"start"` for a class with both `*_Start` and `*_Continue`.
+/// - `"continue-only"` for a class with only `*_Continue`.
+/// - `"start-only"` for a class with only `*_Start`, which the tables never
+///   contain but [UnicodeIdentifierClass::new] can create.
+/// - `"other"` for a class with neither.
+pub fn class_label(c: UnicodeIdentifierClass) -> &'static str {
+    match (c.is_start(), c.is_continue()) {
+        (true, true) => "start",
+        (false, true) => "continue-only",
+        (true, false) => "start-only",
+        (false, false) => "other",
+    }
+}
+
+/// What a token starting with some character could be, as returned by
+/// [token_start_kind].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenStartKind {
+    /// The character can start an identifier.
+    Identifier,
+    /// An ASCII digit, `0` through `9`.
+    Digit,
+    /// `_`, which can't start an identifier under UAX #31 but does in many
+    /// languages.
+    Underscore,
+    /// Anything else.
+    Other,
+}
+
+/// Classifies `c` as the first character of a token, such as for picking the
+/// color of a token in a syntax highlighter, with a single lookup.
+#[inline]
+pub fn token_start_kind(c: char) -> TokenStartKind {
+    match c {
+        '0'..='9' => TokenStartKind::Digit,
+        '_' => TokenStartKind::Underscore,
+        _ if unicode_identifier_class(c).is_start() => {
+            TokenStartKind::Identifier
+        }
+        _ => TokenStartKind::Other,
+    }
+}
+
+/// Returns the number of `char`s in `s` which are start characters.
+#[inline]
+pub fn count_start(s: &str) -> usize {
+    s.chars()
+        .filter(|&c| unicode_identifier_class(c).is_start())
+        .count()
+}
+
+/// Returns the number of `char`s in `s` which are continue characters. Along
+/// with [count_start], this is useful for heuristics such as guessing whether
+/// a line is code or prose.
+#[inline]
+pub fn count_continue(s: &str) -> usize {
+    s.chars()
+        .filter(|&c| unicode_identifier_class(c).is_continue())
+        .count()
+}
+
+/// Maps each character of `iter` to its [UnicodeIdentifierClass], for use in
+/// iterator pipelines.
+///
+/// This is the same as mapping [unicode_identifier_class] over `iter`:
+///
+/// ```rust
+/// use unicode_id_trie_rle::{classes, unicode_identifier_class};
+///
+/// let src = "let x1 = αβ_γ;";
+/// let starts = classes(src.chars()).filter(|c| c.is_start()).count();
+/// let mapped = src
+///     .chars()
+///     .map(unicode_identifier_class)
+///     .filter(|c| c.is_start())
+///     .count();
+/// assert_eq!(starts, mapped);
+/// assert_eq!(starts, 7);
+/// ```
+#[inline]
+pub fn classes<I: Iterator<Item = char>>(
+    iter: I,
+) -> impl Iterator<Item = UnicodeIdentifierClass> {
+    iter.map(unicode_identifier_class)
+}
+
+/// Returns the [UnicodeIdentifierClass] of the first `char` in `s`, or `None`
+/// if `s` is empty.
+#[inline]
+pub fn first_char_class(s: &str) -> Option<UnicodeIdentifierClass> {
+    s.chars().next().map(unicode_identifier_class)
+}
+
+/// Returns the [UnicodeIdentifierClass] of the last `char` in `s`, or `None`
+/// if `s` is empty.
+///
+/// Only the trailing UTF-8 sequence is decoded, so this is constant time
+/// regardless of the length of `s`. This is useful for streaming validators
+/// which need to know whether the input ends in a character that can't end an
+/// identifier.
+#[inline]
+pub fn last_char_class(s: &str) -> Option<UnicodeIdentifierClass> {
+    s.chars().next_back().map(unicode_identifier_class)
+}
+
+/// Returns the class shared by every `char` in `range`, or `None` if they
+/// don't all have the same class or `range` is empty.
+///
+/// This walks the runs of the underlying table rather than querying each
+/// codepoint, so it's cheap even for large ranges.
+pub fn class_is_uniform(
+    range: RangeInclusive<char>,
+) -> Option<UnicodeIdentifierClass> {
+    let (start, end) = (*range.start() as u32, *range.end() as u32);
+    if start > end {
+        return None;
+    }
+
+    let (class, mut next) = run_at(start);
+    while next <= end {
+        if (0xd800..0xe000).contains(&next) {
+            // surrogates aren't `char`s, so they can't be in the range.
+            next = 0xe000;
+            continue;
+        }
+
+        let (other, after) = run_at(next);
+        if other != class {
+            return None;
+        }
+        next = after;
+    }
+
+    Some(UnicodeIdentifierClass(class))
+}
+
+/// Returns the maximal range of `char`s around `c` which all have the same
+/// class as `c`, along with that class, so scanners can skip to the end of the
+/// range instead of classifying every `char` in it. Since surrogates aren't
+/// `char`s, ranges stop at them rather than spanning them.
+///
+/// Like [class_is_uniform], this walks the runs of the underlying table.
+///
+/// ```
+/// use unicode_id_trie_rle::class_run;
+///
+/// let (range, class) = class_run('q');
+/// assert_eq!(range, 'a'..='z');
+/// assert!(class.is_start());
+/// ```
+pub fn class_run(c: char) -> (RangeInclusive<char>, UnicodeIdentifierClass) {
+    let cp = c as u32;
+    let (class, mut end) = run_at(cp);
+    while end <= char::MAX as u32 && end != SURROGATE_START {
+        let (bits, next) = run_at(end);
+        if bits != class {
+            break;
+        }
+        end = next;
+    }
+
+    let mut start = run_start(cp);
+    while start > 0 && start != SURROGATE_END + 1 {
+        let prev = start - 1;
+        if run_at(prev).0 != class {
+            break;
+        }
+        start = run_start(prev);
+    }
+
+    // the surrogates start and end on block boundaries, so runs never
+    // cross into or out of them.
+    let (Some(start), Some(end)) =
+        (char::from_u32(start), char::from_u32(end - 1))
+    else {
+        unreachable!("runs never start or end inside the surrogates");
+    };
+    (start..=end, UnicodeIdentifierClass(class))
+}
+
+/// Returns an iterator over the `char`s of `s` along with their byte offsets
+/// and [UnicodeIdentifierClass], decoding `s` only once.
+///
+/// ```
+/// use unicode_id_trie_rle::classify_str;
+///
+/// let mut iter = classify_str("a1");
+/// let (offset, c, class) = iter.next().unwrap();
+/// assert_eq!((offset, c), (0, 'a'));
+/// assert!(class.is_start() && class.is_continue());
+/// let (offset, c, class) = iter.next().unwrap();
+/// assert_eq!((offset, c), (1, '1'));
+/// assert!(!class.is_start() && class.is_continue());
+/// assert!(iter.next().is_none());
+/// ```
+#[inline]
+pub fn classify_str(
+    s: &str,
+) -> impl Iterator<Item = (usize, char, UnicodeIdentifierClass)> + '_ {
+    s.char_indices()
+        .map(|(i, c)| (i, c, unicode_identifier_class(c)))
+}
+
+/// Returns an iterator over the maximal ranges of `char`s with the
+/// `XID_Start` property, in ascending order.
+///
+/// ```
+/// use unicode_id_trie_rle::xid_start_ranges;
+///
+/// let mut ranges = xid_start_ranges();
+/// assert_eq!(ranges.next(), Some('A'..='Z'));
+/// assert_eq!(ranges.next(), Some('a'..='z'));
+/// ```
+pub fn xid_start_ranges() -> impl Iterator<Item = RangeInclusive<char>> {
+    class_ranges(|bits| bits & IDENTIFIER_START != 0)
+}
+
+/// Returns an iterator over the maximal ranges of `char`s which have neither
+/// the `*_Start` nor the `*_Continue` properties, in ascending order. Along
+/// with the identifier characters, these cover every `char`. Since surrogates
+/// aren't `char`s, the range around them is split in two.
+///
+/// ```
+/// use unicode_id_trie_rle::non_identifier_ranges;
+///
+/// let mut ranges = non_identifier_ranges();
+/// assert_eq!(ranges.next(), Some('\0'..='/'));
+/// assert_eq!(ranges.next(), Some(':'..='@'));
+/// ```
+pub fn non_identifier_ranges() -> impl Iterator<Item = RangeInclusive<char>> {
+    class_ranges(|bits| bits == IDENTIFIER_OTHER)
+}
+
+/// Returns an iterator over the maximal ranges of `char`s whose class bits
+/// satisfy `matches`, in ascending order.
+fn class_ranges(
+    matches: fn(u8) -> bool,
+) -> impl Iterator<Item = RangeInclusive<char>> {
+    let mut cp = 0;
+    core::iter::from_fn(move || {
+        // skip to the start of the next range.
+        loop {
+            if cp > char::MAX as u32 {
+                return None;
+            }
+            // the surrogates start and end on block boundaries, so runs
+            // never cross into or out of them.
+            if cp == SURROGATE_START {
+                cp = SURROGATE_END + 1;
+            }
+            let (bits, next) = run_at(cp);
+            if matches(bits) {
+                break;
+            }
+            cp = next;
+        }
+
+        let start = cp;
+        loop {
+            let (bits, next) = run_at(cp);
+            if !matches(bits) {
+                break;
+            }
+            cp = next;
+            if cp == SURROGATE_START || cp > char::MAX as u32 {
+                break;
+            }
+        }
+
+        let start = char::from_u32(start)?;
+        let end = char::from_u32(cp - 1)?;
+        Some(start..=end)
+    })
+}
+
+/// Collects [xid_start_ranges] into a [Vec].
+#[cfg(feature = "alloc")]
+pub fn collect_xid_start() -> Vec<RangeInclusive<char>> {
+    xid_start_ranges().collect()
+}
+
+/// Writes the effective identifier table to `w` as one line per maximal run
+/// of codepoints with the same class, covering `U+0000..U+10FFFF` in order:
+///
+/// ```text
+/// 000000..00002F start=0 continue=0
+/// 000030..000039 start=0 continue=1
+/// ```
+///
+/// The columns are fixed width and the format won't change, so the dumps of
+/// two builds, such as for different Unicode versions, can be compared with
+/// `diff`.
+#[cfg(feature = "std")]
+pub fn dump_table<W: std::io::Write>(w: &mut W) -> std::io::Result<()> {
+    let mut start = 0;
+    let (mut bits, mut cp) = run_at(0);
+    while cp <= char::MAX as u32 {
+        let (next_bits, next) = run_at(cp);
+        if next_bits != bits {
+            write_dump_line(w, start, cp - 1, bits)?;
+            start = cp;
+            bits = next_bits;
+        }
+        cp = next;
+    }
+    write_dump_line(w, start, char::MAX as u32, bits)
+}
+
+/// Reads newline separated identifiers from `r`, returning each one with
+/// surrounding whitespace trimmed along with whether it is an identifier
+/// according to [str_is_identifier]. Blank lines are skipped.
+///
+/// ```
+/// use unicode_id_trie_rle::validate_reader;
+///
+/// let results = validate_reader("foo\n\n  bar-baz \n".as_bytes()).unwrap();
+/// assert_eq!(
+///     results,
+///     [("foo".to_string(), true), ("bar-baz".to_string(), false)]
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub fn validate_reader<R: std::io::Read>(
+    r: R,
+) -> std::io::Result<Vec<(String, bool)>> {
+    use std::io::BufRead;
+
+    let mut results = Vec::new();
+    for line in std::io::BufReader::new(r).lines() {
+        let line = line?;
+        let name = line.trim();
+        if name.is_empty() {
+            continue;
+        }
+        results.push((name.into(), str_is_identifier(name)));
+    }
+    Ok(results)
+}
+
+#[cfg(feature = "std")]
+fn write_dump_line<W: std::io::Write>(
+    w: &mut W,
+    start: u32,
+    end: u32,
+    bits: u8,
+) -> std::io::Result<()> {
+    writeln!(
+        w,
+        "{start:06X}..{end:06X} start={} continue={}",
+        (bits & IDENTIFIER_START != 0) as u8,
+        (bits & IDENTIFIER_CONTINUE != 0) as u8
+    )
+}
+
+/// The identifier characters as sorted lists of maximal ranges, one for
+/// the `*_Start` characters and one for the `*_Continue` characters.
+///
+/// Unlike the per-`char` functions, this is an owned representation which can
+/// be inspected and combined with other ranges, such as with
+/// [IdentifierRangeSet::intersect].
+///
+/// ```
+/// use unicode_id_trie_rle::IdentifierRangeSet;
+///
+/// let set = IdentifierRangeSet::new();
+/// assert!(set.contains_start('a') && !set.contains_start('1'));
+///
+/// let ascii = set.intersect(&['\0'..='\x7f']);
+/// assert_eq!(ascii.start_ranges(), ['A'..='Z', 'a'..='z']);
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdentifierRangeSet {
+    start: Vec<RangeInclusive<char>>,
+    cont: Vec<RangeInclusive<char>>,
+}
+
+#[cfg(feature = "alloc")]
+impl IdentifierRangeSet {
+    /// Builds the set from the runs of the lookup tables.
+    pub fn new() -> Self {
+        Self {
+            start: xid_start_ranges().collect(),
+            cont: class_ranges(|bits| bits & IDENTIFIER_CONTINUE != 0)
+                .collect(),
+        }
+    }
+
+    /// Returns the maximal ranges of `*_Start` characters, in ascending
+    /// order.
+    pub fn start_ranges(&self) -> &[RangeInclusive<char>] {
+        &self.start
+    }
+
+    /// Returns the maximal ranges of `*_Continue` characters, in ascending
+    /// order.
+    pub fn continue_ranges(&self) -> &[RangeInclusive<char>] {
+        &self.cont
+    }
+
+    /// Returns whether `c` is in one of the `*_Start` ranges.
+    pub fn contains_start(&self, c: char) -> bool {
+        sorted_ranges_contain(&self.start, c)
+    }
+
+    /// Returns whether `c` is in one of the `*_Continue` ranges.
+    pub fn contains_continue(&self, c: char) -> bool {
+        sorted_ranges_contain(&self.cont, c)
+    }
+
+    /// Returns the set containing only the characters of this set which are
+    /// also in one of `ranges`. `ranges` may be in any order and may overlap.
+    pub fn intersect(&self, ranges: &[RangeInclusive<char>]) -> Self {
+        let mut other: Vec<RangeInclusive<char>> =
+            ranges.iter().filter(|x| !x.is_empty()).cloned().collect();
+        other.sort_by_key(|x| *x.start());
+        let mut merged: Vec<RangeInclusive<char>> = Vec::new();
+        for range in other {
+            match merged.last_mut() {
+                Some(last)
+                    if *range.start() as u32 <= *last.end() as u32 + 1 =>
+                {
+                    *last = *last.start()..=*last.end().max(range.end());
+                }
+                _ => merged.push(range),
+            }
+        }
+
+        Self {
+            start: intersect_sorted_ranges(&self.start, &merged),
+            cont: intersect_sorted_ranges(&self.cont, &merged),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Default for IdentifierRangeSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns whether `c` is in one of the sorted, disjoint `ranges`.
+#[cfg(feature = "alloc")]
+fn sorted_ranges_contain(ranges: &[RangeInclusive<char>], c: char) -> bool {
+    let idx = ranges.partition_point(|x| *x.start() <= c);
+    idx != 0 && c <= *ranges[idx - 1].end()
+}
+
+/// Intersects two lists of sorted, disjoint ranges.
+#[cfg(feature = "alloc")]
+fn intersect_sorted_ranges(
+    a: &[RangeInclusive<char>],
+    b: &[RangeInclusive<char>],
+) -> Vec<RangeInclusive<char>> {
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let start = *a[i].start().max(b[j].start());
+        let end = *a[i].end().min(b[j].end());
+        if start <= end {
+            result.push(start..=end);
+        }
+        // whichever range ends first can't overlap anything after the other.
+        if a[i].end() < b[j].end() {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::sync::OnceLock;
+    use unicode_id_trie_rle_derived_core_properties::{
+        IdentifierProperties, identifier_set_fingerprint, parse_default,
+        to_identifier_bits,
+    };
+
+    const MAX_SCALAR: usize = 0x110000;
+
+    fn derived_identifier_table() -> &'static [u8] {
+        static TABLE: OnceLock<Box<[u8]>> = OnceLock::new();
+        TABLE.get_or_init(|| {
+            to_identifier_bits(parse_default(), IdentifierProperties::Xid)
+        })
+    }
+
+    #[test]
+    fn identifier_table_from_custom_arrays() {
+        // 2048 codepoint blocks which all share one leaf, where offsets
+        // 0x100 and up are start characters.
+        static LEVEL1: [u16; 32] = [0; 32];
+        static LEVEL2: [u16; 16] = [0; 16];
+        static OFFSETS: [u16; 2] = [0, 3];
+        static STARTS: [u16; 3] = [0, 0x100, 0x800];
+        static VALUES: [u8; 3] = [0, 3, 0];
+        let table = IdentifierTable::new(
+            11, 4, &LEVEL1, &LEVEL2, &OFFSETS, &STARTS, &VALUES,
+        );
+
+        let class = |cp| {
+            let class = table.classify(char::from_u32(cp).unwrap());
+            (class.is_start(), class.is_continue())
+        };
+        // ASCII always comes from the ASCII table.
+        assert_eq!(class(0x61), (true, true));
+        assert_eq!(class(0x80), (false, false));
+        assert_eq!(class(0x100), (true, true));
+        assert_eq!(class(0x7ff), (true, true));
+        assert_eq!(class(0x800), (false, false));
+        assert_eq!(class(0xf_f900), (true, true));
+        assert_eq!(class(0x10_0100), (false, false));
+    }
+
+    #[test]
+    fn verify_checks_every_version_component() {
+        const _: () = assert!(verify(DATA_VERSION));
+        let (major, minor, update) = DATA_VERSION;
+        assert!(verify((major, minor, update)));
+        assert!(!verify((major + 1, minor, update)));
+        assert!(!verify((major, minor + 1, update)));
+        assert!(!verify((major, minor, update + 1)));
+        assert!(!verify((0, 0, 0)));
+    }
+
+    #[test]
+    fn ascii_table_matches_data() {
+        for cp in 0..START_CODEPOINT {
+            assert_eq!(
+                ASCII_TABLE[cp as usize], DATA_ASCII_CLASSES[cp as usize],
+                "U+{cp:04X}"
+            );
+        }
+    }
+
+    #[test]
+    fn ascii_class_matches_ascii_table() {
+        for cp in 0..START_CODEPOINT {
+            assert_eq!(ascii_class(cp), ASCII_TABLE[cp as usize], "{cp:#x}");
+        }
+    }
+
+    #[test]
+    fn unicode_identifier_class_matches_derived_core_properties() {
+        let table = derived_identifier_table();
+        for cp in 0..=0x10ffff {
+            let Some(ch) = char::from_u32(cp) else {
+                continue;
+            };
+            let expected = table[ch as usize];
+            if cp >= 0x100000 {
+                assert_eq!(
+                    expected, 0,
+                    "derived data marks unsupported codepoint U+{cp:06X}"
+                );
+            }
+
+            let class = unicode_identifier_class(ch);
+            assert_eq!(
+                class.is_start(),
                 expected & IDENTIFIER_START != 0,
                 "ID_Start mismatch at U+{cp:04X}"
             );
             assert_eq!(
-                class.is_continue(),
-                expected & IDENTIFIER_CONTINUE != 0,
-                "ID_Continue mismatch at U+{cp:04X}"
+                class.is_continue(),
+                expected & IDENTIFIER_CONTINUE != 0,
+                "ID_Continue mismatch at U+{cp:04X}"
+            );
+        }
+    }
+
+    #[cfg(feature = "constant-time")]
+    #[test]
+    fn constant_time_lookup_matches_unicode_identifier_class() {
+        for ch in (0..=char::MAX as u32).filter_map(char::from_u32) {
+            assert_eq!(
+                unicode_identifier_class_ct(ch).0,
+                unicode_identifier_class(ch).0,
+                "mismatch at U+{:04X}",
+                ch as u32
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn unicode_identifier_class_proptest(cp in any::<char>()) {
+            let expected = derived_identifier_table()[cp as usize];
+            if (cp as u32) >= 0x100000 {
+                prop_assert_eq!(
+                    expected, 0,
+                    "derived data marks unsupported codepoint U+{:06X}",
+                    cp as u32
+                );
+            }
+
+            let class = unicode_identifier_class(cp);
+            prop_assert_eq!(
+                class.is_start(),
+                expected & IDENTIFIER_START != 0,
+                "ID_Start mismatch at U+{:04X}",
+                cp as u32
+            );
+            prop_assert_eq!(
+                class.is_continue(),
+                expected & IDENTIFIER_CONTINUE != 0,
+                "ID_Continue mismatch at U+{:04X}",
+                cp as u32
+            );
+        }
+    }
+
+    #[cfg(feature = "general_category")]
+    #[test]
+    fn is_letter_start_excludes_non_letters() {
+        for c in ['a', 'Z', '\u{e9}', '\u{3042}', '\u{2070F}'] {
+            assert!(is_letter_start(c), "{c:?} is a letter");
+        }
+        // ROMAN NUMERAL ONE, IDEOGRAPHIC NUMBER ZERO, SCRIPT CAPITAL P and
+        // ESTIMATED SYMBOL are start characters, but not letters.
+        for c in ['\u{2160}', '\u{3007}', '\u{2118}', '\u{212e}'] {
+            assert!(unicode_identifier_class(c).is_start());
+            assert!(!is_letter_start(c), "{c:?} isn't a letter");
+        }
+        for c in ['0', '_', ' ', '\u{300}'] {
+            assert!(!is_letter_start(c), "{c:?} isn't a start character");
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn identifier_len_is_the_longest_identifier_prefix(s in "\\PC{0,12}") {
+            let len = identifier_len(&s);
+            let longest = (1..=s.len())
+                .rev()
+                .filter(|&i| s.is_char_boundary(i))
+                .find(|&i| str_is_identifier(&s[..i]))
+                .unwrap_or(0);
+            prop_assert_eq!(len, longest);
+        }
+
+        #[test]
+        fn identifier_spans_are_identifiers(s in "\\PC{0,24}") {
+            let mut prev_end = 0;
+            for span in identifier_spans(&s) {
+                prop_assert!(prev_end <= span.start);
+                prop_assert!(str_is_identifier(&s[span.clone()]));
+                prop_assert_eq!(identifier_len(&s[span.start..]), span.len());
+                prev_end = span.end;
+            }
+        }
+
+        #[test]
+        fn is_valid_after_agrees_with_str_is_identifier(s in "\\PC{0,12}") {
+            let mut prev = None;
+            let mut valid = !s.is_empty();
+            for c in s.chars() {
+                valid &= is_valid_after(c, prev);
+                prev = Some(c);
+            }
+            prop_assert_eq!(valid, str_is_identifier(&s), "{:?}", s);
+        }
+    }
+
+    #[cfg(feature = "blocks")]
+    #[test]
+    fn identifier_blocks_contain_identifiers() {
+        let table = derived_identifier_table();
+        let mut prev_end = None;
+        for (range, name) in IDENTIFIER_BLOCKS {
+            assert!(prev_end.is_none_or(|prev| prev < *range.start()));
+            prev_end = Some(*range.end());
+
+            let bits = &table[*range.start() as usize..=*range.end() as usize];
+            assert!(
+                bits.iter().any(|&x| x != 0),
+                "{name} has no identifier characters"
+            );
+        }
+
+        let block_of =
+            |cp: u32| IDENTIFIER_BLOCKS.iter().find(|(x, _)| x.contains(&cp));
+        assert_eq!(block_of('a' as u32).map(|x| x.1), Some("Basic Latin"));
+        assert!(block_of(0xE000).is_none());
+    }
+
+    #[test]
+    fn excluded_ranges_reject_identifiers() {
+        // variation selectors are invisible, but they're XID_Continue so
+        // they're normally accepted.
+        let selectors = ['\u{fe00}'..='\u{fe0f}'];
+        assert!(str_is_identifier("a\u{fe0f}"));
+        assert!(!str_is_identifier_excluding("a\u{fe0f}", &selectors));
+        assert!(str_is_identifier_excluding("abc", &selectors));
+        assert!(!str_is_identifier_excluding("1abc", &selectors));
+
+        let sorted = ['a'..='c', 'x'..='x', '\u{e9}'..='\u{e9}'];
+        let unsorted = ['\u{e9}'..='\u{e9}', 'x'..='x', 'a'..='c'];
+        for disallowed in [&sorted[..], &unsorted[..], &[]] {
+            assert!(
+                str_is_identifier_excluding("deF", disallowed),
+                "{disallowed:?}"
+            );
+            for s in ["b", "fox", "caf\u{e9}"] {
+                assert_eq!(
+                    str_is_identifier_excluding(s, disallowed),
+                    disallowed.is_empty(),
+                    "{s} with {disallowed:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn identifiers_eq_ignoring_ascii_case() {
+        assert!(identifiers_eq_ignore_ascii_case("Foo", "foo"));
+        assert!(identifiers_eq_ignore_ascii_case("Größe", "gRößE"));
+        assert!(!identifiers_eq_ignore_ascii_case("Ärger", "ärger"));
+        assert!(!identifiers_eq_ignore_ascii_case("foo", "bar"));
+        assert!(!identifiers_eq_ignore_ascii_case("1foo", "1FOO"));
+        assert!(!identifiers_eq_ignore_ascii_case("", ""));
+    }
+
+    #[test]
+    fn joiners_are_valid_after_continue_characters() {
+        for c in ['\u{200c}', '\u{200d}'] {
+            assert!(is_valid_after(c, Some('a')));
+            assert!(is_valid_after(c, Some('1')));
+            assert!(is_valid_after(c, Some('\u{915}')));
+            assert!(!is_valid_after(c, Some('-')));
+            assert!(!is_valid_after(c, Some(' ')));
+            assert!(!is_valid_after(c, None));
+        }
+        assert!(is_valid_after('b', Some('\u{200d}')));
+        assert!(!is_valid_after('-', Some('a')));
+        assert!(!is_valid_after('_', None));
+    }
+
+    #[test]
+    fn identifier_fragments() {
+        assert!(is_identifier_fragment("123"));
+        assert!(!str_is_identifier("123"));
+        assert!(is_identifier_fragment("bar"));
+        assert!(is_identifier_fragment("_x\u{200d}y"));
+
+        // ZWJ and ZWNJ are continue characters in the bundled data, so they
+        // can end a fragment just like they can end an identifier.
+        for s in ["a\u{200d}", "a\u{200c}"] {
+            assert_eq!(is_identifier_fragment(s), str_is_identifier(s));
+            assert_eq!(
+                is_identifier_fragment(s),
+                unicode_identifier_class(s.chars().last().unwrap())
+                    .is_continue()
+            );
+        }
+
+        assert!(!is_identifier_fragment(""));
+        assert!(!is_identifier_fragment("a-b"));
+        assert!(!is_identifier_fragment("::"));
+    }
+
+    proptest! {
+        #[test]
+        fn identifiers_are_fragments_starting_with_a_start(
+            s in "\\PC{0,8}",
+        ) {
+            let starts = first_char_class(&s).is_some_and(|x| x.is_start());
+            prop_assert_eq!(
+                str_is_identifier(&s),
+                starts && is_identifier_fragment(&s),
+                "{:?}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn needs_raw_escape_for_keywords() {
+        assert!(RAW_ESCAPABLE_KEYWORDS.is_sorted());
+
+        for s in ["fn", "match", "type", "async", "gen", "abstract"] {
+            assert!(needs_raw_escape(s), "{s} needs escaping");
+        }
+        for s in ["foo", "_x", "union", "größe", "Fn", "matches"] {
+            assert!(!needs_raw_escape(s), "{s} isn't a keyword");
+        }
+        for s in ["self", "Self", "super", "crate"] {
+            assert!(!needs_raw_escape(s), "{s} can't be a raw identifier");
+        }
+        for s in ["", "_", "1fn", "r#fn"] {
+            assert!(!needs_raw_escape(s), "{s} isn't an identifier");
+        }
+    }
+
+    #[test]
+    fn class_labels_cover_every_combination() {
+        let label = |start, cont| {
+            class_label(UnicodeIdentifierClass::new(start, cont))
+        };
+        assert_eq!(label(true, true), "start");
+        assert_eq!(label(false, true), "continue-only");
+        assert_eq!(label(true, false), "start-only");
+        assert_eq!(label(false, false), "other");
+        assert_eq!(class_label(unicode_identifier_class('a')), "start");
+        assert_eq!(
+            class_label(unicode_identifier_class('0')),
+            "continue-only"
+        );
+        assert_eq!(class_label(unicode_identifier_class('-')), "other");
+    }
+
+    #[test]
+    fn first_char_class_of_empty_and_non_empty_input() {
+        assert!(first_char_class("").is_none());
+
+        let class = first_char_class("a1").unwrap();
+        assert!(class.is_start() && class.is_continue());
+        let class = first_char_class("1a").unwrap();
+        assert!(!class.is_start() && class.is_continue());
+        let class = first_char_class("\u{2070F}x").unwrap();
+        assert!(class.is_start());
+        let class = first_char_class(" ").unwrap();
+        assert!(!class.is_start() && !class.is_continue());
+    }
+
+    #[test]
+    fn last_char_class_reads_only_the_tail() {
+        assert!(last_char_class("").is_none());
+
+        // U+2070F (a CJK Extension B ideograph) is XID_Start and takes four
+        // bytes in UTF-8.
+        let mut s = "a".repeat(1 << 20);
+        s.push('\u{2070f}');
+        let class = last_char_class(&s).unwrap();
+        assert!(class.is_start());
+        assert!(class.is_continue());
+
+        s.push('-');
+        let class = last_char_class(&s).unwrap();
+        assert!(!class.is_start());
+        assert!(!class.is_continue());
+    }
+
+    #[test]
+    fn leading_underscore_option() {
+        let options = IdentifierOptions {
+            allow_leading_underscore: true,
+            ..Default::default()
+        };
+        for s in ["_", "_x", "__", "_\u{2070f}"] {
+            assert!(!str_is_identifier(s), "{s:?}");
+            assert!(str_is_identifier_with(s, options), "{s:?}");
+        }
+
+        for s in ["", "_-", "1_", "-_"] {
+            assert!(!str_is_identifier_with(s, options), "{s:?}");
+        }
+    }
+
+    #[cfg(feature = "general_category")]
+    #[test]
+    fn mark_start_option() {
+        let options = IdentifierOptions {
+            allow_mark_start: true,
+            ..Default::default()
+        };
+        // COMBINING ACUTE ACCENT, DEVANAGARI SIGN VISARGA (Mc) and a
+        // combining mark outside the BMP.
+        for s in ["\u{301}", "\u{301}a", "\u{903}x1", "\u{1d165}\u{1d166}"] {
+            assert!(!str_is_identifier(s), "{s:?}");
+            assert!(str_is_identifier_with(s, options), "{s:?}");
+        }
+        // digits and connector punctuation are continue characters, but not
+        // marks, and ENCLOSING CIRCLE isn't a continue character.
+        for s in ["1a", "_a", "\u{203f}a", "\u{20dd}a", "a\u{20dd}"] {
+            assert!(!str_is_identifier_with(s, options), "{s:?}");
+        }
+        assert!(str_is_identifier_with("a\u{301}", options));
+    }
+
+    #[test]
+    fn reject_default_ignorable_option() {
+        let options = IdentifierOptions {
+            reject_default_ignorable: true,
+            ..Default::default()
+        };
+        // SOFT HYPHEN isn't an identifier character at all, while the
+        // variation selector and HANGUL FILLER are, so only the option
+        // rejects them.
+        for s in ["foo\u{ad}bar", "\u{ad}foo"] {
+            assert!(!str_is_identifier(s), "{s:?}");
+            assert!(!str_is_identifier_with(s, options), "{s:?}");
+        }
+        for s in ["foo\u{fe0f}bar", "foo\u{e0100}", "\u{3164}foo"] {
+            assert!(str_is_identifier(s), "{s:?}");
+            assert!(!str_is_identifier_with(s, options), "{s:?}");
+        }
+        // the medial joiners are still allowed.
+        for s in ["foo", "a\u{200c}b", "a\u{200d}b"] {
+            assert!(str_is_identifier_with(s, options), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn max_bytes_option() {
+        let options = IdentifierOptions::new().max_bytes(4).build();
+        for s in ["a", "abcd", "\u{e9}\u{e9}"] {
+            assert!(str_is_identifier_with(s, options), "{s:?}");
+        }
+        for s in ["abcde", "\u{e9}\u{e9}a", "\u{1d400}a"] {
+            assert!(!str_is_identifier_with(s, options), "{s:?}");
+        }
+        assert_eq!(identifier_len_with("abcdef", options), 4);
+        assert_eq!(identifier_len_with("a\u{e9}\u{e9}", options), 3);
+        assert_eq!(identifier_len_with("ab cdef", options), 2);
+
+        // the limit applies after trailing joiners are trimmed.
+        let options = IdentifierOptions::new()
+            .max_bytes(4)
+            .trim_trailing_joiners(true)
+            .build();
+        assert!(str_is_identifier_with("abcd\u{200d}", options));
+    }
+
+    #[test]
+    fn private_use_option() {
+        let options = IdentifierOptions {
+            allow_private_use: true,
+            ..Default::default()
+        };
+        for s in ["\u{e000}", "\u{f8ff}x", "a\u{f0000}", "a\u{10fffd}b"] {
+            assert!(!str_is_identifier(s), "{s:?}");
+            assert!(str_is_identifier_with(s, options), "{s:?}");
+        }
+        // only the BMP Private Use Area can start an identifier, and the
+        // noncharacters at the end of planes 15 and 16 aren't private use.
+        for s in ["\u{f0000}", "\u{100000}a", "a\u{ffffe}", "a\u{10ffff}"] {
+            assert!(!str_is_identifier_with(s, options), "{s:?}");
+        }
+        assert_eq!(identifier_len_with("x\u{e123}\u{f0001}-", options), 8);
+        assert_eq!(identifier_len("x\u{e123}\u{f0001}-"), 1);
+    }
+
+    #[test]
+    fn equivalent_identifiers_fold_joiners() {
+        let options = IdentifierOptions::new().fold_joiners(true).build();
+        assert!(identifiers_equivalent("a\u{200d}b", "ab", options));
+        assert!(identifiers_equivalent("a\u{200c}b", "a\u{200d}b", options));
+        assert!(identifiers_equivalent("a\u{200c}\u{200d}b", "ab", options));
+        assert!(!identifiers_equivalent("a\u{200d}b", "ac", options));
+        assert!(!identifiers_equivalent("\u{200d}", "", options));
+
+        // without the option the joiners are significant.
+        let defaults = IdentifierOptions::default();
+        assert!(identifiers_equivalent("ab", "ab", defaults));
+        assert!(!identifiers_equivalent("a\u{200d}b", "ab", defaults));
+        assert!(!identifiers_equivalent("1", "1", defaults));
+
+        let options = IdentifierOptions::new()
+            .fold_joiners(true)
+            .trim_trailing_joiners(true)
+            .build();
+        assert!(identifiers_equivalent("ab\u{200d}", "a\u{200c}b", options));
+        let options =
+            IdentifierOptions::new().trim_trailing_joiners(true).build();
+        assert!(identifiers_equivalent("ab\u{200d}", "ab", options));
+        assert!(!identifiers_equivalent("a\u{200d}b", "ab", options));
+    }
+
+    #[test]
+    fn skip_bom_option() {
+        let options = IdentifierOptions::new().skip_bom(true).build();
+        for s in [
+            "ab\u{feff}c",
+            "\u{feff}abc",
+            "abc\u{feff}",
+            "a\u{feff}\u{feff}1",
+        ] {
+            assert!(!str_is_identifier(s), "{s:?}");
+            assert!(str_is_identifier_with(s, options), "{s:?}");
+            assert_eq!(identifier_len_with(s, options), s.len(), "{s:?}");
+        }
+        for s in ["", "\u{feff}", "\u{feff}1", "a\u{feff}-"] {
+            assert!(!str_is_identifier_with(s, options), "{s:?}");
+        }
+        assert_eq!(identifier_len("ab\u{feff}c"), 2);
+        assert_eq!(identifier_len_with("ab\u{feff}c d", options), 6);
+        assert!(identifiers_equivalent("a\u{feff}b", "ab", options));
+
+        // U+FEFF is default ignorable, but skipping it takes precedence.
+        let options = IdentifierOptions::new()
+            .skip_bom(true)
+            .reject_default_ignorable(true)
+            .build();
+        assert!(str_is_identifier_with("ab\u{feff}c", options));
+    }
+
+    #[test]
+    fn trim_trailing_joiners_option() {
+        let options = IdentifierOptions {
+            trim_trailing_joiners: true,
+            ..Default::default()
+        };
+        for s in ["a\u{200d}", "a\u{200c}", "ab\u{200c}\u{200d}", "a\u{200d}b"]
+        {
+            assert!(str_is_identifier_with(s, options), "{s:?}");
+        }
+        for s in ["", "\u{200d}", "\u{200c}\u{200d}", "a-\u{200d}"] {
+            assert!(!str_is_identifier_with(s, options), "{s:?}");
+        }
+
+        assert_eq!(identifier_len_with("a\u{200d}", options), 1);
+        assert_eq!(identifier_len_with("ab\u{200c}\u{200d}", options), 2);
+        assert_eq!(identifier_len_with("a\u{200d}b", options), 5);
+        assert_eq!(identifier_len_with("a\u{200d} b", options), 1);
+        assert_eq!(identifier_len_with("\u{200d}a", options), 0);
+        // ZWNJ and ZWJ are continue characters in the bundled data, so
+        // without the option they're part of the identifier.
+        assert_eq!(identifier_len("a\u{200d}"), 4);
+    }
+
+    #[test]
+    fn const_lookup_matches_unicode_identifier_class() {
+        for ch in (0..=char::MAX as u32).filter_map(char::from_u32) {
+            assert_eq!(
+                class_bits_const(ch as u32),
+                unicode_identifier_class(ch).0,
+                "mismatch at U+{:04X}",
+                ch as u32
+            );
+        }
+    }
+
+    #[test]
+    fn validate_identifier_reports_offsets() {
+        assert_eq!(validate_identifier(""), Err(0));
+        assert_eq!(validate_identifier("_x"), Err(0));
+        assert_eq!(validate_identifier("\u{e9}t\u{e9}"), Ok(()));
+        assert_eq!(validate_identifier("\u{e9}-"), Err(2));
+        assert_eq!(validate_identifier("a\u{200d}b"), Ok(()));
+        assert_eq!(validate_identifier("ab\u{ad}c"), Err(2));
+    }
+
+    proptest! {
+        #[test]
+        fn classifier_cache_matches_unicode_identifier_class(
+            chars in prop::collection::vec(any::<char>(), 0..32),
+            block in 0u32..0x110000 >> SHIFT,
+            offsets in prop::collection::vec(0u32..1 << SHIFT, 0..32),
+        ) {
+            let mut cache = ClassifierCache::new();
+            // mix arbitrary characters with runs inside a single block, so
+            // both hits and misses are covered.
+            let same_block = offsets
+                .iter()
+                .filter_map(|&x| char::from_u32((block << SHIFT) | x));
+            for c in chars.into_iter().chain(same_block) {
+                prop_assert_eq!(
+                    cache.classify(c).0,
+                    unicode_identifier_class(c).0,
+                    "mismatch at {:?}",
+                    c
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn validate_identifier_matches_str_is_identifier(s in "\\PC{0,12}") {
+            let valid = validate_identifier(&s);
+            prop_assert_eq!(valid.is_ok(), str_is_identifier(&s));
+            if let Err(offset) = valid {
+                prop_assert!(s.is_char_boundary(offset));
+            }
+        }
+    }
+
+    #[test]
+    fn rust_2021_identifiers() {
+        // examples from the identifiers chapter of the Rust reference.
+        for s in ["foo", "_identifier", "Москва", "東京"] {
+            assert!(is_rust_2021_identifier(s), "{s:?}");
+        }
+        // a leading `_` is fine unless it's the whole identifier, and
+        // keywords are identifiers lexically.
+        for s in ["__", "_0", "self"] {
+            assert!(is_rust_2021_identifier(s), "{s:?}");
+        }
+        for s in ["", "_", "1x", "r#foo", "a-b", "\u{200d}"] {
+            assert!(!is_rust_2021_identifier(s), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn rust_2021_identifiers_with_a_trailing_joiner() {
+        // only accepted because ZWJ is XID_Continue in the Unicode 17 data,
+        // not because Rust special-cases it.
+        assert!(unicode_identifier_class('\u{200d}').is_continue());
+        assert!(is_rust_2021_identifier("x\u{200d}"));
+    }
+
+    #[test]
+    fn u32s_is_identifier_validates_codepoints() {
+        let cps = |s: &str| s.chars().map(u32::from).collect::<Vec<_>>();
+        assert!(u32s_is_identifier(&cps("caf\u{e9}_1")));
+        assert!(u32s_is_identifier(&cps("\u{1d400}x")));
+        assert!(!u32s_is_identifier(&cps("1x")));
+        assert!(!u32s_is_identifier(&[]));
+
+        for bad in [0xd800, 0xdfff, 0x110000, u32::MAX] {
+            let mut id = cps("abc");
+            id.insert(1, bad);
+            assert!(!u32s_is_identifier(&id), "{bad:#x}");
+            assert!(!u32s_is_identifier(&[bad]), "{bad:#x}");
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn sanitized_identifiers() {
+        let options = IdentifierOptions::new()
+            .allow_leading_underscore(true)
+            .build();
+        for (s, expected) in [
+            ("123abc", "_123abc"),
+            ("foo-bar", "foo_bar"),
+            ("", "_"),
+            ("-x", "__x"),
+            ("caf\u{e9} au lait", "caf\u{e9}_au_lait"),
+        ] {
+            let sanitized = sanitize_identifier(s, '_');
+            assert_eq!(sanitized, expected, "{s:?}");
+            assert!(str_is_identifier_with(&sanitized, options), "{s:?}");
+        }
+
+        assert_eq!(sanitize_identifier("foo-bar", 'x'), "fooxbar");
+        assert!(str_is_identifier(&sanitize_identifier("foo bar!", 'x')));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn normalized_identifiers_only_allocate_when_invalid() {
+        for s in ["foo", "caf\u{e9}", "a\u{200d}b", "_", "_1x"] {
+            let normalized = normalize_identifier(s);
+            assert!(matches!(normalized, Cow::Borrowed(x) if x == s), "{s:?}");
+        }
+        for (s, expected) in [
+            ("foo-bar", "foo_bar"),
+            ("1x", "_1x"),
+            ("123abc", "_123abc"),
+            ("", "_"),
+        ] {
+            let normalized = normalize_identifier(s);
+            assert!(
+                matches!(&normalized, Cow::Owned(x) if x == expected),
+                "{s:?}"
+            );
+            // normalizing is idempotent, so the second pass never allocates.
+            assert!(
+                matches!(
+                    normalize_identifier(&normalized),
+                    Cow::Borrowed(x) if x == expected
+                ),
+                "{s:?}"
+            );
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    #[should_panic(expected = "isn't a continue character")]
+    fn sanitize_rejects_invalid_replacement() {
+        sanitize_identifier("foo-bar", '-');
+    }
+
+    #[cfg(feature = "alloc")]
+    proptest! {
+        #[test]
+        fn normalize_identifier_is_idempotent(s in any::<String>()) {
+            let normalized = normalize_identifier(&s);
+            prop_assert!(
+                matches!(
+                    normalize_identifier(&normalized),
+                    Cow::Borrowed(x) if x == normalized
+                ),
+                "{:?} normalized to {:?}",
+                s,
+                normalized
+            );
+        }
+    }
+
+    #[test]
+    fn resolve_checks_validity_and_membership() {
+        let symbols = ["_", "a", "caf\u{e9}", "foo", "foo-bar", "x1"];
+        for resolve in [resolve, resolve_sorted] {
+            assert_eq!(resolve("foo", &symbols), Some("foo"));
+            assert_eq!(resolve("caf\u{e9}", &symbols), Some("caf\u{e9}"));
+            assert_eq!(resolve("x1", &symbols), Some("x1"));
+            assert_eq!(resolve("bar", &symbols), None);
+            assert_eq!(resolve("fo", &symbols), None);
+            // in the table, but not identifiers.
+            assert_eq!(resolve("_", &symbols), None);
+            assert_eq!(resolve("foo-bar", &symbols), None);
+            assert_eq!(resolve("", &symbols), None);
+            assert_eq!(resolve("foo", &[]), None);
+        }
+    }
+
+    #[test]
+    fn resolve_accepts_unsorted_symbols() {
+        let symbols = ["x1", "foo-bar", "foo", "caf\u{e9}", "a", "_"];
+        assert_eq!(resolve("a", &symbols), Some("a"));
+        assert_eq!(resolve("x1", &symbols), Some("x1"));
+        assert_eq!(resolve("caf\u{e9}", &symbols), Some("caf\u{e9}"));
+        assert_eq!(resolve("bar", &symbols), None);
+        assert_eq!(resolve("foo-bar", &symbols), None);
+    }
+
+    #[test]
+    fn find_first_identifier() {
+        assert_eq!(find_identifier("foo bar"), Some(0..3));
+        assert_eq!(find_identifier("--> 1, \u{e9}t\u{e9}"), Some(7..12));
+        assert_eq!(find_identifier("(_x)"), Some(2..3));
+        assert_eq!(find_identifier("2abc"), Some(1..4));
+        assert_eq!(find_identifier(""), None);
+        assert_eq!(find_identifier("123 + (4 * _)"), None);
+    }
+
+    #[test]
+    fn next_identifier_start_from_cursor() {
+        let s = "  foo(\u{e9}t\u{e9}, 1\u{1d400}) ";
+        // Before an identifier, and at its first character.
+        assert_eq!(next_identifier_start(s, 0), Some(2));
+        assert_eq!(next_identifier_start(s, 2), Some(2));
+        // Inside an identifier, every following character is a start too.
+        assert_eq!(next_identifier_start(s, 3), Some(3));
+        // Between identifiers, skipping punctuation and the digit.
+        assert_eq!(next_identifier_start(s, 5), Some(6));
+        assert_eq!(next_identifier_start(s, 11), Some(14));
+        // In the middle of `é` and of U+1D400.
+        assert_eq!(next_identifier_start(s, 7), Some(8));
+        assert_eq!(next_identifier_start(s, 15), None);
+        // After the last identifier, and past the end of `s`.
+        assert_eq!(next_identifier_start(s, 18), None);
+        assert_eq!(next_identifier_start(s, 100), None);
+        assert_eq!(next_identifier_start("", 0), None);
+    }
+
+    #[test]
+    fn count_start_and_continue() {
+        // 8 letters (2 of them astral), 2 digits, `_`, a combining mark, and
+        // punctuation and spaces which are neither.
+        let s = "let x_1 = \u{1d400}\u{e9}2e\u{301} + \u{1e900};";
+        assert_eq!(count_start(s), 8);
+        assert_eq!(count_continue(s), 12);
+        assert_eq!(count_start(""), 0);
+        assert_eq!(count_continue(" ;-"), 0);
+    }
+
+    #[test]
+    fn token_start_kinds() {
+        for c in ['a', 'Z', '\u{e9}', '\u{65e5}'] {
+            assert_eq!(
+                token_start_kind(c),
+                TokenStartKind::Identifier,
+                "{c:?}"
             );
         }
+        for c in ['0', '9'] {
+            assert_eq!(token_start_kind(c), TokenStartKind::Digit, "{c:?}");
+        }
+        assert_eq!(token_start_kind('_'), TokenStartKind::Underscore);
+        // non-ASCII digits are continue-only, not `Digit`.
+        for c in ['-', '(', ' ', '\u{660}', '\u{300}'] {
+            assert_eq!(token_start_kind(c), TokenStartKind::Other, "{c:?}");
+        }
+    }
+
+    #[test]
+    fn identifier_error_code_round_trips() {
+        let decode = |code: u32| {
+            (
+                code >> ERROR_KIND_SHIFT,
+                (code & ERROR_OFFSET_MASK) as usize,
+            )
+        };
+        for (s, expected) in [
+            ("", (ERROR_EMPTY, 0)),
+            ("1st", (ERROR_INVALID_START, 0)),
+            ("\u{200d}a", (ERROR_INVALID_START, 0)),
+            ("foo-bar", (ERROR_INVALID_CONTINUE, 3)),
+            ("h\u{e9}llo world", (ERROR_INVALID_CONTINUE, 6)),
+        ] {
+            assert_eq!(decode(identifier_error_code(s)), expected, "{s:?}");
+        }
+        // ZWNJ and ZWJ are continue characters in current data, so
+        // ERROR_TRAILING_JOINER only happens with overrides.
+        assert_eq!(identifier_error_code("a\u{200c}"), 0);
     }
 
     proptest! {
         #[test]
-        fn unicode_identifier_class_proptest(cp in any::<char>()) {
-            let expected = derived_identifier_table()[cp as usize];
-            if (cp as u32) >= 0x100000 {
+        fn identifier_error_code_agrees_with_str_is_identifier(
+            chars in prop::collection::vec(any::<char>(), 0..16),
+        ) {
+            let s: String = chars.into_iter().collect();
+            let code = identifier_error_code(&s);
+            prop_assert_eq!(code == 0, str_is_identifier(&s));
+            if code != 0 {
                 prop_assert_eq!(
-                    expected, 0,
-                    "derived data marks unsupported codepoint U+{:06X}",
-                    cp as u32
+                    Err((code & ERROR_OFFSET_MASK) as usize),
+                    validate_identifier(&s)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bytes_is_identifier_validates_utf8() {
+        for s in ["a", "héllo", "日本語", "x\u{2070f}1", "a\u{200c}b"] {
+            assert!(bytes_is_identifier(s.as_bytes()), "{s:?}");
+        }
+        for s in ["", "1a", "a-b", "\u{2070f}-"] {
+            assert!(!bytes_is_identifier(s.as_bytes()), "{s:?}");
+        }
+
+        let invalid: [&[u8]; 7] = [
+            b"a\xff",
+            b"\xc3",              // truncated 2 byte sequence
+            b"a\xc3",             // truncated at the end
+            b"\xc0\x81",          // overlong 'A'
+            b"a\xe0\x80\xaf",     // overlong '/'
+            b"a\xed\xa0\x80",     // U+D800
+            b"a\xf4\x90\x80\x80", // U+110000
+        ];
+        for bytes in invalid {
+            assert!(std::str::from_utf8(bytes).is_err());
+            assert!(!bytes_is_identifier(bytes), "{bytes:?}");
+        }
+    }
+
+    #[cfg(feature = "debug")]
+    proptest! {
+        #[test]
+        fn classify_traced_matches_unicode_identifier_class(cp in any::<char>()) {
+            let (class, path) = classify_traced(cp);
+            prop_assert_eq!(class.0, unicode_identifier_class(cp).0);
+            if cp.is_ascii() {
+                prop_assert_eq!(path, Path::Ascii);
+            } else if cp as u32 >= 0x100000 {
+                prop_assert_eq!(path, Path::AboveRange);
+            } else {
+                prop_assert_ne!(path, Path::Ascii);
+                prop_assert_ne!(path, Path::AboveRange);
+            }
+        }
+    }
+
+    #[test]
+    fn trie_matches_derived_core_properties() {
+        let table = derived_identifier_table();
+        for cp in START_CODEPOINT..0x100000 {
+            if char::from_u32(cp).is_none() {
+                continue;
+            }
+            assert_eq!(
+                classify_trie_only(cp).0,
+                table[cp as usize],
+                "mismatch at U+{cp:04X}"
+            );
+        }
+
+        // plane 15 is entirely private use, so the trie should have nothing
+        // there even though the lookups never short circuit it.
+        for cp in 0xF0000..0x100000 {
+            assert_eq!(classify_trie_only(cp).0, IDENTIFIER_OTHER);
+        }
+    }
+
+    #[test]
+    fn block_boundaries_match_derived_core_properties() {
+        let table = derived_identifier_table();
+        for block in 1..BLOCK_COUNT as u32 {
+            let boundary = block << SHIFT;
+            for cp in boundary - 1..=boundary + 1 {
+                let Some(ch) = char::from_u32(cp) else {
+                    continue;
+                };
+                assert_eq!(
+                    unicode_identifier_class(ch).0,
+                    table[cp as usize],
+                    "mismatch at U+{cp:04X} near block {block}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn xid_start_ranges_match_derived_core_properties() {
+        let table = derived_identifier_table();
+        let mut expected = vec![0u8; MAX_SCALAR];
+        let mut prev_end = None;
+        for range in xid_start_ranges() {
+            let (start, end) = (*range.start() as u32, *range.end() as u32);
+            assert!(start <= end);
+            // ranges are maximal, so they can't touch the previous one.
+            assert!(prev_end.is_none_or(|prev| prev + 1 < start));
+            prev_end = Some(end);
+            for cp in start..=end {
+                expected[cp as usize] = IDENTIFIER_START;
+            }
+        }
+
+        for (cp, &bits) in table.iter().enumerate() {
+            assert_eq!(
+                bits & IDENTIFIER_START,
+                expected[cp],
+                "mismatch at U+{cp:04X}"
+            );
+        }
+    }
+
+    #[test]
+    fn structure_counts_are_consistent() {
+        let summary = structure_summary();
+        assert!(summary.leaves > 0);
+        assert!(summary.runs >= summary.leaves);
+        assert!(summary.level2_tables > 0);
+        assert_eq!(LEAF_OFFSETS.len(), LEAF_COUNT + 1);
+        assert_eq!(LEAF_OFFSETS[LEAF_COUNT] as usize, LEAF_RUN_STARTS.len());
+        assert_eq!(LEAF_RUN_STARTS.len(), RUN_COUNT + LEAF_COUNT);
+        assert_eq!(LEAF_RUN_VALUES.len(), LEAF_RUN_STARTS.len());
+        assert_eq!(LEVEL2_TABLES.len(), LEVEL2_TABLE_COUNT * LOWER_SIZE);
+        assert!(
+            LEVEL2_TABLES
+                .iter()
+                .all(|&leaf| (leaf as usize) < summary.leaves)
+        );
+    }
+
+    #[test]
+    fn fingerprint_matches_derived_core_properties() {
+        let table =
+            to_identifier_bits(parse_default(), IdentifierProperties::Xid);
+        assert_eq!(
+            IDENTIFIER_SET_FINGERPRINT,
+            identifier_set_fingerprint(&table)
+        );
+    }
+
+    #[cfg(feature = "versioned")]
+    #[test]
+    fn builtin_version_is_bundled() {
+        assert_eq!(unicode_version(), (17, 0, 0));
+        assert!(bundled_unicode_versions().any(|x| x == unicode_version()));
+        for s in ["foo", "héllo", "1a", "_"] {
+            assert_eq!(
+                str_is_identifier_version(s, unicode_version()),
+                Some(str_is_identifier(s)),
+                "{s:?}"
+            );
+            assert_eq!(str_is_identifier_version(s, (1, 0, 0)), None);
+        }
+    }
+
+    #[cfg(feature = "versioned")]
+    #[test]
+    fn versioned_tables_match_their_data() {
+        let Some(dir) = option_env!("UNICODE_VERSIONS_DIR") else {
+            assert!(VERSIONED_TABLES.is_empty());
+            return;
+        };
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let data = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            let header = data.lines().next().unwrap();
+            let version: Vec<u8> = header
+                .trim_start_matches("# DerivedCoreProperties-")
+                .trim_end_matches(".txt")
+                .split('.')
+                .map(|x| x.parse().unwrap())
+                .collect();
+            let version = (version[0], version[1], version[2]);
+            if version == unicode_version() {
+                continue;
+            }
+
+            let table = VERSIONED_TABLES
+                .iter()
+                .find(|table| table.version == version)
+                .unwrap();
+            let parsed = unicode_id_trie_rle_derived_core_properties::parse(
+                data.as_bytes(),
+            )
+            .unwrap();
+            let expected =
+                to_identifier_bits(&parsed, IdentifierProperties::Xid);
+            for cp in 0..0x100000 {
+                let Some(c) = char::from_u32(cp) else {
+                    continue;
+                };
+                assert_eq!(
+                    table.table.classify(c).0,
+                    expected[cp as usize],
+                    "{version:?} mismatch at U+{cp:04X}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn vectorized_tables_are_aligned() {
+        assert_eq!(LEVEL2_TABLES.as_ptr() as usize % 16, 0);
+        assert_eq!(LEAF_RUN_STARTS.as_ptr() as usize % 16, 0);
+        assert_eq!(LEAF_RUN_VALUES.as_ptr() as usize % 16, 0);
+    }
+
+    #[cfg(feature = "other_id")]
+    #[test]
+    fn other_id_properties_are_subsets_of_id_properties() {
+        let table =
+            to_identifier_bits(parse_default(), IdentifierProperties::Id);
+        for ch in (0..=char::MAX as u32).filter_map(char::from_u32) {
+            let bits = table[ch as usize];
+            if is_other_id_start(ch) {
+                assert!(bits & IDENTIFIER_START != 0, "{ch:?} isn't ID_Start");
+            }
+            if is_other_id_continue(ch) {
+                assert!(
+                    bits & IDENTIFIER_CONTINUE != 0,
+                    "{ch:?} isn't ID_Continue"
                 );
             }
+        }
+
+        assert!(is_other_id_start('\u{2118}'));
+        assert!(!is_other_id_start('a'));
+        assert!(is_other_id_continue('\u{b7}'));
+        assert!(!is_other_id_continue('0'));
+    }
+
+    #[test]
+    fn identifier_and_non_identifier_ranges_partition_chars() {
+        let mut seen = vec![0u8; MAX_SCALAR];
+        let identifiers = class_ranges(|bits| bits != IDENTIFIER_OTHER);
+        for range in identifiers.chain(non_identifier_ranges()) {
+            for ch in range {
+                seen[ch as usize] += 1;
+            }
+        }
+
+        for (cp, &count) in seen.iter().enumerate() {
+            let expected = match cp as u32 {
+                SURROGATE_START..=SURROGATE_END => 0,
+                _ => 1,
+            };
+            assert_eq!(count, expected, "U+{cp:04X} covered {count} times");
+        }
+    }
+
+    #[test]
+    fn non_identifier_ranges_are_maximal() {
+        let mut prev_end = None;
+        for range in non_identifier_ranges() {
+            let (start, end) = (*range.start() as u32, *range.end() as u32);
+            assert!(start <= end);
+            assert!(unicode_identifier_class(*range.start()).0 == 0);
+            // only the split around the surrogates may touch.
+            assert!(prev_end.is_none_or(|prev| {
+                prev + 1 < start
+                    || (prev == SURROGATE_START - 1
+                        && start == SURROGATE_END + 1)
+            }));
+            prev_end = Some(end);
+        }
+        assert_eq!(prev_end, Some(char::MAX as u32));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn collect_xid_start_matches_iterator() {
+        assert!(collect_xid_start().into_iter().eq(xid_start_ranges()));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn dump_table_round_trips() {
+        let mut dump = Vec::new();
+        dump_table(&mut dump).unwrap();
+        let dump = String::from_utf8(dump).unwrap();
+
+        let mut next = 0;
+        let mut prev_bits = None;
+        for line in dump.lines() {
+            let (range, flags) = line.split_once(' ').unwrap();
+            let (start, end) = range.split_once("..").unwrap();
+            let start = u32::from_str_radix(start, 16).unwrap();
+            let end = u32::from_str_radix(end, 16).unwrap();
+            let bits = match flags {
+                "start=0 continue=0" => IDENTIFIER_OTHER,
+                "start=0 continue=1" => IDENTIFIER_CONTINUE,
+                "start=1 continue=1" => IDENTIFIER_START | IDENTIFIER_CONTINUE,
+                _ => panic!("unexpected flags in {line:?}"),
+            };
+            assert_eq!(line.len(), "000000..000000 ".len() + flags.len());
+            assert_eq!(start, next, "gap before {line:?}");
+            assert!(start <= end);
+            assert_ne!(prev_bits, Some(bits), "{line:?} isn't maximal");
+            for ch in (start..=end).filter_map(char::from_u32) {
+                assert_eq!(unicode_identifier_class(ch).0, bits, "{ch:?}");
+            }
+            next = end + 1;
+            prev_bits = Some(bits);
+        }
+        assert_eq!(next, char::MAX as u32 + 1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn validate_reader_reports_each_line() {
+        let input = "foo\r\n\
+                     \t_bar\n\
+                     \n\
+                     r\u{e9}sum\u{e9}\n\
+                     1st\n   \n\
+                     kebab-case\n\
+                     last";
+        let expected = [
+            ("foo", true),
+            ("_bar", false),
+            ("r\u{e9}sum\u{e9}", true),
+            ("1st", false),
+            ("kebab-case", false),
+            ("last", true),
+        ]
+        .map(|(name, valid)| (name.to_string(), valid));
+        assert_eq!(validate_reader(input.as_bytes()).unwrap(), expected);
+
+        let invalid_utf8: &[u8] = b"foo\n\xff\n";
+        assert!(validate_reader(invalid_utf8).is_err());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn identifier_range_set_matches_unicode_identifier_class() {
+        let set = IdentifierRangeSet::new();
+        for ch in (0..=char::MAX as u32).filter_map(char::from_u32) {
+            let class = unicode_identifier_class(ch);
+            assert_eq!(set.contains_start(ch), class.is_start(), "{ch:?}");
+            assert_eq!(
+                set.contains_continue(ch),
+                class.is_continue(),
+                "{ch:?}"
+            );
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn identifier_range_set_intersection() {
+        let set = IdentifierRangeSet::new();
+        let ascii = set.intersect(&['a'..='z', '\0'..='9', 'A'..='Z']);
+        assert_eq!(ascii.start_ranges(), ['A'..='Z', 'a'..='z']);
+        assert_eq!(ascii.continue_ranges(), ['0'..='9', 'A'..='Z', 'a'..='z']);
+
+        // overlapping and adjacent input ranges give the same result as
+        // their union.
+        let split = set.intersect(&['m'..='z', 'a'..='f', 'c'..='l']);
+        assert_eq!(split, set.intersect(&['a'..='z']));
+        assert_eq!(split.start_ranges(), ['a'..='z']);
+
+        assert_eq!(set.intersect(&[]).start_ranges(), []);
+        let empty = set.intersect(&['z'..='a']);
+        assert!(empty.continue_ranges().is_empty());
+        assert_eq!(set.intersect(&['\0'..=char::MAX]), set);
+    }
+
+    #[test]
+    fn class_is_uniform_over_known_ranges() {
+        let letters = class_is_uniform('a'..='z').unwrap();
+        assert!(letters.is_start() && letters.is_continue());
+        let digits = class_is_uniform('0'..='9').unwrap();
+        assert!(!digits.is_start() && digits.is_continue());
+        let cjk = class_is_uniform('\u{4e00}'..='\u{9fff}').unwrap();
+        assert!(cjk.is_start());
+        let unsupported = class_is_uniform('\u{100000}'..=char::MAX).unwrap();
+        assert!(!unsupported.is_start() && !unsupported.is_continue());
+
+        assert!(class_is_uniform('A'..='z').is_none());
+        assert!(class_is_uniform('a'..='\u{10ffff}').is_none());
+        assert!(class_is_uniform('z'..='a').is_none());
+    }
+
+    proptest! {
+        #[test]
+        fn class_is_uniform_matches_per_char_classes(
+            start in any::<char>(),
+            len in 0u32..4096,
+        ) {
+            let end = char::from_u32(start as u32 + len).unwrap_or(start);
+            let mut classes = (start..=end).map(|c| unicode_identifier_class(c).0);
+            let first = classes.next().unwrap();
+            let expected = classes.all(|x| x == first).then_some(first);
+            prop_assert_eq!(class_is_uniform(start..=end).map(|x| x.0), expected);
+        }
+    }
 
+    #[test]
+    fn as_continue_clears_only_the_start_bit() {
+        for (start, cont) in [(false, false), (false, true), (true, true)] {
+            let class = UnicodeIdentifierClass::new(start, cont).as_continue();
+            assert!(!class.is_start());
+            assert_eq!(class.is_continue(), cont);
+        }
+        let class = unicode_identifier_class('a').as_continue();
+        assert_eq!(class.0, IDENTIFIER_CONTINUE);
+    }
+
+    // unicode-ident carries its own copy of the Unicode data, so this fails
+    // when the two crates are built from different Unicode versions.
+    proptest! {
+        #[test]
+        fn unicode_identifier_class_matches_unicode_ident(cp in any::<char>()) {
             let class = unicode_identifier_class(cp);
+            let (start, cont) = (
+                unicode_ident::is_xid_start(cp),
+                unicode_ident::is_xid_continue(cp),
+            );
             prop_assert_eq!(
                 class.is_start(),
-                expected & IDENTIFIER_START != 0,
-                "ID_Start mismatch at U+{:04X}",
-                cp as u32
+                start,
+                "XID_Start mismatch at U+{:04X}: this crate says {}, \
+                 unicode-ident says {}",
+                cp as u32,
+                class.is_start(),
+                start
             );
             prop_assert_eq!(
                 class.is_continue(),
-                expected & IDENTIFIER_CONTINUE != 0,
-                "ID_Continue mismatch at U+{:04X}",
-                cp as u32
+                cont,
+                "XID_Continue mismatch at U+{:04X}: this crate says {}, \
+                 unicode-ident says {}",
+                cp as u32,
+                class.is_continue(),
+                cont
+            );
+        }
+    }
+
+    #[test]
+    fn class_runs_are_shared_within_a_run() {
+        for c in ['a', 'm', 'z'] {
+            assert_eq!(class_run(c).0, 'a'..='z');
+        }
+        assert_eq!(class_run('0').0, '0'..='9');
+        assert_eq!(class_run('_').0, '_'..='_');
+        // the range around the surrogates is split in two.
+        assert_eq!(*class_run('\u{d7ff}').0.end(), '\u{d7ff}');
+        assert_eq!(*class_run('\u{e000}').0.start(), '\u{e000}');
+        assert_eq!(*class_run(char::MAX).0.end(), char::MAX);
+        assert_eq!(*class_run('\0').0.start(), '\0');
+    }
+
+    proptest! {
+        #[test]
+        fn class_run_is_the_maximal_run_around_c(c in any::<char>()) {
+            let (range, class) = class_run(c);
+            prop_assert!(range.contains(&c));
+            prop_assert_eq!(class.0, unicode_identifier_class(c).0);
+            prop_assert_eq!(
+                class_is_uniform(range.clone()).map(|x| x.0),
+                Some(class.0)
+            );
+            for bound in [*range.start(), *range.end()] {
+                prop_assert_eq!(class_run(bound).0, range.clone());
+            }
+
+            let before = (*range.start() as u32).checked_sub(1);
+            if let Some(before) = before.and_then(char::from_u32) {
+                prop_assert_ne!(unicode_identifier_class(before).0, class.0);
+            }
+            if let Some(after) = char::from_u32(*range.end() as u32 + 1) {
+                prop_assert_ne!(unicode_identifier_class(after).0, class.0);
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn merge_is_commutative_and_associative(
+            a in 0u8..4,
+            b in 0u8..4,
+            c in 0u8..4,
+        ) {
+            let class = UnicodeIdentifierClass;
+            let ab = UnicodeIdentifierClass::merge(class(a), class(b));
+            prop_assert_eq!(ab.0, a | b);
+            prop_assert_eq!(
+                ab.0,
+                UnicodeIdentifierClass::merge(class(b), class(a)).0
+            );
+            prop_assert_eq!(
+                UnicodeIdentifierClass::merge(ab, class(c)).0,
+                UnicodeIdentifierClass::merge(
+                    class(a),
+                    UnicodeIdentifierClass::merge(class(b), class(c)),
+                )
+                .0
             );
         }
     }
 
+    proptest! {
+        #[test]
+        fn ascii_slices_match_general_path(
+            chars in prop::collection::vec(0u8..0x80, 0..16),
+        ) {
+            let chars: Vec<char> = chars.into_iter().map(char::from).collect();
+            let general = !chars.is_empty()
+                && unicode_identifier_class(chars[0]).is_start()
+                && chars.iter().all(|&c| unicode_identifier_class(c).is_continue());
+            prop_assert_eq!(is_identifier(&chars), general, "{:?}", chars);
+        }
+    }
+
     proptest! {
         #[test]
         fn str_and_slice_identifier_agree(chars in prop::collection::vec(any::<char>(), 0..16)) {
@@ -277,5 +3843,51 @@ mod tests {
                 string
             );
         }
+
+        #[test]
+        fn str_and_bytes_identifier_agree(chars in prop::collection::vec(any::<char>(), 0..16)) {
+            let string: String = chars.iter().copied().collect();
+            prop_assert_eq!(
+                bytes_is_identifier(string.as_bytes()),
+                str_is_identifier(&string),
+                "bytes/str_is_identifier disagreement on {:?}",
+                string
+            );
+        }
+
+        #[test]
+        fn static_table_matches_unicode_identifier_class(cp in any::<char>()) {
+            let expected = unicode_identifier_class(cp);
+            let class = STATIC_TABLE.classify(cp);
+            prop_assert_eq!(class.0, expected.0, "U+{:04X}", cp as u32);
+        }
+
+        #[test]
+        fn u32s_and_slice_identifier_agree(chars in prop::collection::vec(any::<char>(), 0..16)) {
+            let cps: Vec<u32> = chars.iter().map(|&c| c as u32).collect();
+            prop_assert_eq!(
+                u32s_is_identifier(&cps),
+                is_identifier(&chars),
+                "u32s/is_identifier disagreement on {:?}",
+                chars
+            );
+        }
+
+        #[test]
+        fn bytes_is_identifier_rejects_invalid_utf8(
+            chars in prop::collection::vec(any::<char>(), 0..16),
+            invalid in prop::sample::select(&[0x80u8, 0xc0, 0xf5, 0xff][..]),
+        ) {
+            let mut bytes = String::from_iter(chars).into_bytes();
+            let at = bytes.len() / 2;
+            // find a char boundary, so the inserted byte is what breaks it.
+            let at = (0..=at)
+                .rev()
+                .find(|&i| bytes.get(i).is_none_or(|&b| b & 0xc0 != 0x80))
+                .unwrap();
+            bytes.insert(at, invalid);
+            prop_assert!(core::str::from_utf8(&bytes).is_err());
+            prop_assert!(!bytes_is_identifier(&bytes), "{:?}", bytes);
+        }
     }
 }
@@ -0,0 +1,8 @@
+#![cfg(feature = "macros")]
+
+#[test]
+fn ident_macro() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/ui/ident-pass.rs");
+    cases.compile_fail("tests/ui/ident-fail.rs");
+}
@@ -0,0 +1,6 @@
+use unicode_id_trie_rle::ident;
+
+fn main() {
+    let _ = ident!("foo-bar");
+    let _ = ident!("");
+}
@@ -0,0 +1,7 @@
+use unicode_id_trie_rle::ident;
+
+const NAMES: [&str; 3] = [ident!("foo"), ident!("r\u{e9}sum\u{e9}"), ident!("a\u{200d}b")];
+
+fn main() {
+    assert_eq!(NAMES, ["foo", "r\u{e9}sum\u{e9}", "a\u{200d}b"]);
+}
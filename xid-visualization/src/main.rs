@@ -5,10 +5,30 @@
 //! Codepoints are arranged from left-to-right with 1024 per line.
 //!
 //! CLI usage:
-//! - `xid-visualization [output.svg]` (defaults to `xid-visualization.svg`)
-//! - `cargo run -p xid-visualization -- [output.svg]`
+//! - `xid-visualization [--csv runs.csv] [--scale N] [output.svg]` (defaults
+//!   to `xid-visualization.svg`)
+//! - `cargo run -p xid-visualization -- [--csv runs.csv] [--scale N]
+//!   [output.svg]`
 //!
-//! The tool prints the legend, mapping, and counts to stdout.
+//! The Unicode data is read from `UNICODE_DATA_PATH` if it is set, and
+//! otherwise from the `DerivedCoreProperties.txt` at the repository root.
+//!
+//! The tool prints the legend, mapping, and counts, overall and per plane, to
+//! stdout. If the output is `-`, the SVG is written to stdout instead and the
+//! report goes to stderr.
+//!
+//! With `--csv`, every run of codepoints with the same `XID_Start` and
+//! `XID_Continue` values is also written as a `start_hex,end_hex,is_start,
+//! is_continue` row, in ascending order. Runs with neither property are left
+//! out, so the only gaps between rows are codepoints which aren't part of an
+//! identifier.
+//!
+//! With `--scale N`, every `N`x`N` cell of codepoints in the grid is drawn as a
+//! single pixel, which makes the image `N` times smaller in each dimension.
+//! The pixel takes the class most codepoints in the cell have. Ties are broken
+//! in favor of `XID_Start`, then `XID_Continue` only, then neither, so sparse
+//! identifier characters stay visible in the thumbnail. Cells at the right and
+//! bottom edges which hang off the grid only count the codepoints inside it.
 
 use std::{
     collections::{BTreeMap, HashSet},
@@ -18,22 +38,39 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use unicode_id_trie_rle::{UnicodeIdentifierClass, class_label};
 use unicode_id_trie_rle_derived_core_properties as derived_core_properties;
 
 const MAX_CODEPOINT: u32 = 0x10FFFF;
 const IMAGE_WIDTH: u32 = 1024;
-const IMAGE_HEIGHT: u32 = ((MAX_CODEPOINT + 1) as u32) / IMAGE_WIDTH;
+const IMAGE_HEIGHT: u32 = (MAX_CODEPOINT + 1) / IMAGE_WIDTH;
 const DEFAULT_OUTPUT: &str = "xid-visualization.svg";
 
-const USAGE: &str = "Usage: xid-visualization [output.svg]\n\nDefaults:\n  output xid-visualization.svg";
+const USAGE: &str = "Usage: xid-visualization [--csv runs.csv] [--scale N] [output.svg | -]\n\nDefaults:\n  output xid-visualization.svg\n  scale 1";
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Ordered by precedence, so ties in [classify_cell] go to the greater class.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 enum Class {
     Background,
     ContinueOnly,
     Start,
 }
 
+impl Class {
+    fn identifier_class(self) -> UnicodeIdentifierClass {
+        match self {
+            Class::Background => UnicodeIdentifierClass::new(false, false),
+            Class::ContinueOnly => UnicodeIdentifierClass::new(false, true),
+            Class::Start => UnicodeIdentifierClass::new(true, true),
+        }
+    }
+
+    /// The CSS class of the rectangles drawn for this class.
+    fn css_class(self) -> &'static str {
+        class_label(self.identifier_class())
+    }
+}
+
 #[derive(Clone, Copy)]
 struct Rgb {
     r: u8,
@@ -55,14 +92,26 @@ struct Palette {
 
 struct Args {
     output: PathBuf,
+    csv: Option<PathBuf>,
+    /// The side length, in codepoints, of the cell drawn as each pixel.
+    scale: u32,
 }
 
+const PLANE_COUNT: usize = 17;
+
 struct Stats {
     start: usize,
     continue_count: usize,
     start_only: usize,
     continue_only: usize,
     none: usize,
+    planes: [PlaneStats; PLANE_COUNT],
+}
+
+#[derive(Clone, Copy, Default)]
+struct PlaneStats {
+    start: usize,
+    continue_count: usize,
 }
 
 fn main() {
@@ -81,13 +130,8 @@ fn main() {
 }
 
 fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
-    let derived_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join("..")
-        .join("DerivedCoreProperties.txt");
-    let props = {
-        let file = File::open(&derived_path)?;
-        derived_core_properties::parse(file)?
-    };
+    let derived_path = derived_core_properties::default_data_path();
+    let props = derived_core_properties::parse_default();
 
     let palette = Palette {
         background: Rgb {
@@ -107,25 +151,73 @@ fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         },
     };
 
-    write_svg(&args.output, &props, &palette)?;
+    if let Some(csv) = &args.csv {
+        let mut writer = BufWriter::new(File::create(csv)?);
+        write_csv(&mut writer, props)?;
+        writer.flush()?;
+    }
+
+    let stats = compute_stats(props);
+    if args.output == Path::new("-") {
+        let mut writer = BufWriter::new(io::stdout().lock());
+        write_svg(&mut writer, props, &palette, args.scale)?;
+        writer.flush()?;
+        // stdout is taken by the image, so the report goes to stderr.
+        print_report(
+            &mut io::stderr().lock(),
+            args,
+            &derived_path,
+            &palette,
+            &stats,
+        )?;
+    } else {
+        let mut writer = BufWriter::new(File::create(&args.output)?);
+        write_svg(&mut writer, props, &palette, args.scale)?;
+        writer.flush()?;
+        print_report(
+            &mut io::stdout().lock(),
+            args,
+            &derived_path,
+            &palette,
+            &stats,
+        )?;
+    }
 
-    let stats = compute_stats(&props);
-    print_report(args, &derived_path, &palette, &stats);
     Ok(())
 }
 
 fn parse_args() -> Result<Args, String> {
     let mut output: Option<PathBuf> = None;
+    let mut csv: Option<PathBuf> = None;
+    let mut scale = 1;
     let mut args = env::args().skip(1);
-
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "--help" | "-h" => {
                 println!("{USAGE}");
                 std::process::exit(0);
             }
+            "--csv" => {
+                let Some(path) = args.next() else {
+                    return Err("--csv requires a path".to_string());
+                };
+                csv = Some(PathBuf::from(path));
+            }
+            "--scale" => {
+                let Some(value) = args.next() else {
+                    return Err("--scale requires a value".to_string());
+                };
+                scale = match value.parse::<u32>() {
+                    Ok(n) if n > 0 => n,
+                    _ => {
+                        return Err(format!(
+                            "--scale must be a positive integer, got {value}"
+                        ));
+                    }
+                };
+            }
             _ => {
-                if arg.starts_with('-') {
+                if arg.starts_with('-') && arg != "-" {
                     return Err(format!("unknown flag: {arg}"));
                 }
                 if output.is_none() {
@@ -139,86 +231,113 @@ fn parse_args() -> Result<Args, String> {
 
     Ok(Args {
         output: output.unwrap_or_else(|| PathBuf::from(DEFAULT_OUTPUT)),
+        csv,
+        scale,
     })
 }
 
-fn write_svg(
-    path: &Path,
+fn write_svg<W: Write>(
+    writer: &mut W,
     props: &BTreeMap<u32, HashSet<String>>,
     palette: &Palette,
+    scale: u32,
 ) -> io::Result<()> {
-    let mut writer = BufWriter::new(File::create(path)?);
-
     let background = palette.background.hex();
     let start = palette.start.hex();
     let cont = palette.continue_only.hex();
+    let (width, height) = image_size(scale);
 
     writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
     writeln!(
         writer,
         "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">",
-        IMAGE_WIDTH, IMAGE_HEIGHT, IMAGE_WIDTH, IMAGE_HEIGHT
+        width, height, width, height
     )?;
     writeln!(writer, "  <defs>")?;
     writeln!(writer, "    <style>")?;
-    writeln!(writer, "      .start {{ fill: {start}; }}")?;
-    writeln!(writer, "      .cont {{ fill: {cont}; }}")?;
+    writeln!(
+        writer,
+        "      .{} {{ fill: {start}; }}",
+        Class::Start.css_class()
+    )?;
+    writeln!(
+        writer,
+        "      .{} {{ fill: {cont}; }}",
+        Class::ContinueOnly.css_class()
+    )?;
     writeln!(writer, "    </style>")?;
     writeln!(writer, "  </defs>")?;
     writeln!(
         writer,
         "  <rect x=\"0\" y=\"0\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
-        IMAGE_WIDTH, IMAGE_HEIGHT, background
+        width, height, background
     )?;
 
-    for row in 0..IMAGE_HEIGHT {
-        let y = row;
-        let row_base = row * IMAGE_WIDTH;
+    for y in 0..height {
         let mut run_start = 0u32;
-        let mut run_class = 'run_class: {
-            let set = if let Some(x) = props.get(&row_base) {
-                x
-            } else {
-                break 'run_class Class::Background;
-            };
-            if set.contains("XID_Start") {
-                Class::Start
-            } else if set.contains("XID_Continue") {
-                Class::ContinueOnly
-            } else {
-                Class::Background
-            }
-        };
+        let mut run_class = classify_cell(props, 0, y, scale);
 
-        for col in 1..IMAGE_WIDTH {
-            let class = 'class: {
-                let set = if let Some(x) = props.get(&(row_base + col)) {
-                    x
-                } else {
-                    break 'class Class::Background;
-                };
-                if set.contains("XID_Start") {
-                    Class::Start
-                } else if set.contains("XID_Continue") {
-                    Class::ContinueOnly
-                } else {
-                    Class::Background
-                }
-            };
+        for col in 1..width {
+            let class = classify_cell(props, col, y, scale);
             if class != run_class {
-                emit_run(&mut writer, run_class, y, run_start, col)?;
+                emit_run(writer, run_class, y, run_start, col)?;
                 run_class = class;
                 run_start = col;
             }
         }
 
-        emit_run(&mut writer, run_class, y, run_start, IMAGE_WIDTH)?;
+        emit_run(writer, run_class, y, run_start, width)?;
     }
 
     writeln!(writer, "</svg>")?;
     Ok(())
 }
 
+/// Returns the width and height of the image when each pixel covers a
+/// `scale`x`scale` cell of codepoints.
+fn image_size(scale: u32) -> (u32, u32) {
+    (IMAGE_WIDTH.div_ceil(scale), IMAGE_HEIGHT.div_ceil(scale))
+}
+
+/// Returns the class most codepoints in the `scale`x`scale` cell drawn at
+/// pixel (`x`, `y`) have, preferring [Class::Start], then
+/// [Class::ContinueOnly], then [Class::Background] on ties.
+fn classify_cell(
+    props: &BTreeMap<u32, HashSet<String>>,
+    x: u32,
+    y: u32,
+    scale: u32,
+) -> Class {
+    let mut counts = [0u32; 3];
+    let rows = y * scale..((y + 1) * scale).min(IMAGE_HEIGHT);
+    for row in rows {
+        let cols = x * scale..((x + 1) * scale).min(IMAGE_WIDTH);
+        for col in cols {
+            counts[classify_pixel(props, row * IMAGE_WIDTH + col) as usize] +=
+                1;
+        }
+    }
+
+    [Class::Background, Class::ContinueOnly, Class::Start]
+        .into_iter()
+        .max_by_key(|&class| (counts[class as usize], class))
+        .unwrap()
+}
+
+fn classify_pixel(props: &BTreeMap<u32, HashSet<String>>, cp: u32) -> Class {
+    let Some(set) = props.get(&cp) else {
+        return Class::Background;
+    };
+
+    if set.contains("XID_Start") {
+        Class::Start
+    } else if set.contains("XID_Continue") {
+        Class::ContinueOnly
+    } else {
+        Class::Background
+    }
+}
+
 fn emit_run<W: Write>(
     writer: &mut W,
     class: Class,
@@ -226,20 +345,64 @@ fn emit_run<W: Write>(
     start_col: u32,
     end_col: u32,
 ) -> io::Result<()> {
-    let class_name = match class {
-        Class::Start => "start",
-        Class::ContinueOnly => "cont",
-        Class::Background => return Ok(()),
-    };
+    if class == Class::Background {
+        return Ok(());
+    }
 
     let width = end_col - start_col;
     writeln!(
         writer,
         "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"1\" class=\"{}\"/>",
-        start_col, y, width, class_name
+        start_col,
+        y,
+        width,
+        class.css_class()
     )
 }
 
+fn write_csv<W: Write>(
+    writer: &mut W,
+    props: &BTreeMap<u32, HashSet<String>>,
+) -> io::Result<()> {
+    let bits = |cp: u32| {
+        let cp_props = props.get(&cp);
+        (
+            cp_props.is_some_and(|x| x.contains("XID_Start")),
+            cp_props.is_some_and(|x| x.contains("XID_Continue")),
+        )
+    };
+
+    writeln!(writer, "start_hex,end_hex,is_start,is_continue")?;
+    let mut run_start = 0;
+    let mut run_bits = bits(0);
+    for cp in 1..=MAX_CODEPOINT + 1 {
+        let cp_bits = if cp <= MAX_CODEPOINT {
+            bits(cp)
+        } else {
+            (false, false)
+        };
+        if cp_bits == run_bits {
+            continue;
+        }
+
+        let (is_start, is_continue) = run_bits;
+        if is_start || is_continue {
+            writeln!(
+                writer,
+                "{:04X},{:04X},{},{}",
+                run_start,
+                cp - 1,
+                is_start as u8,
+                is_continue as u8
+            )?;
+        }
+        run_start = cp;
+        run_bits = cp_bits;
+    }
+
+    Ok(())
+}
+
 fn compute_stats(props: &BTreeMap<u32, HashSet<String>>) -> Stats {
     let mut stats = Stats {
         start: 0,
@@ -247,6 +410,7 @@ fn compute_stats(props: &BTreeMap<u32, HashSet<String>>) -> Stats {
         start_only: 0,
         continue_only: 0,
         none: 0,
+        planes: [PlaneStats::default(); PLANE_COUNT],
     };
 
     for cp in 0..=MAX_CODEPOINT {
@@ -255,11 +419,14 @@ fn compute_stats(props: &BTreeMap<u32, HashSet<String>>) -> Stats {
         let has_continue =
             cp_props.is_some_and(|x| x.contains("XID_Continue"));
 
+        let plane = &mut stats.planes[(cp >> 16) as usize];
         if has_start {
             stats.start += 1;
+            plane.start += 1;
         }
         if has_continue {
             stats.continue_count += 1;
+            plane.continue_count += 1;
         }
         if has_start && !has_continue {
             stats.start_only += 1;
@@ -275,36 +442,214 @@ fn compute_stats(props: &BTreeMap<u32, HashSet<String>>) -> Stats {
     stats
 }
 
-fn print_report(
+fn print_report<W: Write>(
+    writer: &mut W,
     args: &Args,
     derived_path: &Path,
     palette: &Palette,
     stats: &Stats,
-) {
+) -> io::Result<()> {
     let background = palette.background.hex();
     let start = palette.start.hex();
     let cont = palette.continue_only.hex();
 
-    println!("Output: {}", args.output.display());
-    println!("Derived data: {}", derived_path.display());
-    println!(
-        "Image size: {}x{} px (one pixel per codepoint).",
-        IMAGE_WIDTH, IMAGE_HEIGHT
-    );
-    println!(
+    writeln!(writer, "Output: {}", args.output.display())?;
+    writeln!(writer, "Derived data: {}", derived_path.display())?;
+    let (width, height) = image_size(args.scale);
+    if args.scale == 1 {
+        writeln!(
+            writer,
+            "Image size: {width}x{height} px (one pixel per codepoint)."
+        )?;
+    } else {
+        writeln!(
+            writer,
+            "Image size: {width}x{height} px (one pixel per {0}x{0} cell of codepoints, colored by the most common class).",
+            args.scale
+        )?;
+    }
+    writeln!(
+        writer,
         "Mapping: 1024 codepoints per row, left-to-right, then the next line (x = cp & 0x3FF, y = cp >> 10)."
-    );
-    println!("Colors:");
-    println!("  XID_Start: {start}");
-    println!("  XID_Continue only (not XID_Start): {cont}");
-    println!("  None: {background}");
-    println!("Counts:");
-    println!("  XID_Start: {}", stats.start);
-    println!("  XID_Continue only: {}", stats.continue_only);
-    println!("  Codepoints with neither: {}", stats.none);
-    println!(
+    )?;
+    writeln!(writer, "Colors:")?;
+    writeln!(writer, "  XID_Start: {start}")?;
+    writeln!(writer, "  XID_Continue only (not XID_Start): {cont}")?;
+    writeln!(writer, "  None: {background}")?;
+    writeln!(writer, "Counts:")?;
+    writeln!(writer, "  XID_Start: {}", stats.start)?;
+    writeln!(writer, "  XID_Continue only: {}", stats.continue_only)?;
+    writeln!(writer, "  Codepoints with neither: {}", stats.none)?;
+    writeln!(
+        writer,
         "  XID_Continue total (includes XID_Start): {}",
         stats.continue_count
-    );
-    println!("  XID_Start only: {}", stats.start_only);
+    )?;
+    writeln!(writer, "  XID_Start only: {}", stats.start_only)?;
+    writeln!(writer, "Counts per plane (XID_Start, XID_Continue):")?;
+    for (plane, counts) in stats.planes.iter().enumerate() {
+        let name = match plane_name(plane) {
+            Some(name) => format!(" ({name})"),
+            None => String::new(),
+        };
+        writeln!(
+            writer,
+            "  Plane {plane}{name}: {}, {}",
+            counts.start, counts.continue_count
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns the abbreviation of a plane's name, for the planes which have one.
+fn plane_name(plane: usize) -> Option<&'static str> {
+    match plane {
+        0 => Some("BMP"),
+        1 => Some("SMP"),
+        2 => Some("SIP"),
+        3 => Some("TIP"),
+        14 => Some("SSP"),
+        15 => Some("SPUA-A"),
+        16 => Some("SPUA-B"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_pixel_picks_the_strongest_class() {
+        let props = derived_core_properties::parse(
+            "0030 ; XID_Continue\n\
+             0041 ; XID_Start\n\
+             0041 ; XID_Continue\n\
+             0020 ; White_Space\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(classify_pixel(&props, 0x41), Class::Start);
+        assert_eq!(classify_pixel(&props, 0x30), Class::ContinueOnly);
+        assert_eq!(classify_pixel(&props, 0x20), Class::Background);
+        assert_eq!(classify_pixel(&props, 0x21), Class::Background);
+    }
+
+    #[test]
+    fn classify_cell_picks_the_dominant_class() {
+        let props = derived_core_properties::parse(
+            "0000 ; XID_Start\n\
+             0001 ; XID_Continue\n\
+             0400 ; XID_Continue\n\
+             0002 ; XID_Start\n\
+             0003 ; XID_Continue\n\
+             0402 ; XID_Start\n\
+             0403 ; XID_Continue\n\
+             0004..0005 ; XID_Continue\n\
+             0006 ; XID_Start\n\
+             03FF ; XID_Start\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        // Cell covering U+0000, U+0001, U+0400 and U+0401.
+        assert_eq!(classify_cell(&props, 0, 0, 2), Class::ContinueOnly);
+        // Two starts and two continues: the tie goes to Start.
+        assert_eq!(classify_cell(&props, 1, 0, 2), Class::Start);
+        // Two continues and two background codepoints.
+        assert_eq!(classify_cell(&props, 2, 0, 2), Class::ContinueOnly);
+        // One start and three background codepoints.
+        assert_eq!(classify_cell(&props, 3, 0, 2), Class::Background);
+        // The last column of a 3x3 grid hangs off the edge, leaving only
+        // U+03FF, U+07FF and U+0BFF.
+        assert_eq!(classify_cell(&props, 341, 0, 3), Class::Background);
+        assert_eq!(classify_cell(&props, 1023, 0, 1), Class::Start);
+
+        assert_eq!(image_size(1), (IMAGE_WIDTH, IMAGE_HEIGHT));
+        assert_eq!(image_size(2), (512, 544));
+        assert_eq!(image_size(3), (342, 363));
+
+        let palette = Palette {
+            background: Rgb { r: 0, g: 0, b: 0 },
+            continue_only: Rgb { r: 1, g: 1, b: 1 },
+            start: Rgb { r: 2, g: 2, b: 2 },
+        };
+        let mut out = Vec::new();
+        write_svg(&mut out, &props, &palette, 2).unwrap();
+        let svg = String::from_utf8(out).unwrap();
+        assert!(svg.contains("width=\"512\" height=\"544\""));
+        let rects: Vec<_> =
+            svg.lines().filter(|x| x.contains("class=")).collect();
+        assert_eq!(
+            rects,
+            [
+                "  <rect x=\"0\" y=\"0\" width=\"1\" height=\"1\" class=\"continue-only\"/>",
+                "  <rect x=\"1\" y=\"0\" width=\"1\" height=\"1\" class=\"start\"/>",
+                "  <rect x=\"2\" y=\"0\" width=\"1\" height=\"1\" class=\"continue-only\"/>",
+            ]
+        );
+    }
+
+    #[test]
+    fn write_csv_emits_identifier_runs_in_order() {
+        let props = derived_core_properties::parse(
+            "0030..0039 ; XID_Continue\n\
+             0041..005A ; XID_Start\n\
+             0041..005A ; XID_Continue\n\
+             005F ; XID_Continue\n\
+             10FFFF ; XID_Start\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        let mut out = Vec::new();
+        write_csv(&mut out, &props).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "start_hex,end_hex,is_start,is_continue\n\
+             0030,0039,0,1\n\
+             0041,005A,1,1\n\
+             005F,005F,0,1\n\
+             10FFFF,10FFFF,1,0\n"
+        );
+    }
+
+    #[test]
+    fn per_plane_counts_sum_to_global_counts() {
+        let props = derived_core_properties::parse(
+            "0030..0039 ; XID_Continue\n\
+             0041..005A ; XID_Start\n\
+             0041..005A ; XID_Continue\n\
+             10000..1000B ; XID_Start\n\
+             10000..1000B ; XID_Continue\n\
+             E0100..E01EF ; XID_Continue\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        let stats = compute_stats(&props);
+        let start: usize = stats.planes.iter().map(|x| x.start).sum();
+        let cont: usize = stats.planes.iter().map(|x| x.continue_count).sum();
+        assert_eq!(start, stats.start);
+        assert_eq!(cont, stats.continue_count);
+        assert_eq!(
+            (stats.planes[0].start, stats.planes[0].continue_count),
+            (26, 36)
+        );
+        assert_eq!(
+            (stats.planes[1].start, stats.planes[1].continue_count),
+            (12, 12)
+        );
+        assert_eq!(
+            (stats.planes[14].start, stats.planes[14].continue_count),
+            (0, 240)
+        );
+
+        let real = compute_stats(derived_core_properties::parse_default());
+        let start: usize = real.planes.iter().map(|x| x.start).sum();
+        let cont: usize = real.planes.iter().map(|x| x.continue_count).sum();
+        assert_eq!(start, real.start);
+        assert_eq!(cont, real.continue_count);
+    }
 }